@@ -1,12 +1,14 @@
 // <https://github.com/torvalds/linux/blob/master/tools/power/cpupower/utils/helpers/sysfs.c>
 
 use sysfs::api::cpu;
-use sysfs::api::cpu::cpufreq;
+use sysfs::api::cpu::cpufreq::Policy;
 
 fn main() {
-    for cpu_num in 0..cpu::count_cpus().unwrap() {
+    for cpu_num in cpu::policies().unwrap().into_keys() {
+        let policy = Policy::new(cpu_num);
+        let snapshot = policy.read_all();
         println!(
-            r#"/sys/devices/system/cpu/cpufreq/policy{}:
+            r#"{}:
     affected_cpus               - {:?}
     bios_limit                  - {:?}
     cpuinfo_cur_freq            - {:?}
@@ -20,21 +22,23 @@ fn main() {
     scaling_governor            - {:?}
     scaling_max_freq            - {:?}
     scaling_setspeed            - {:?}
+    time_in_state               - {:?}
 "#,
-            cpu_num,
-            cpufreq::affected_cpus(cpu_num),
-            cpufreq::bios_limit(cpu_num),
-            cpufreq::cpuinfo_cur_freq(cpu_num),
-            cpufreq::cpuinfo_max_freq(cpu_num),
-            cpufreq::cpuinfo_min_freq(cpu_num),
-            cpufreq::cpuinfo_transition_latency(cpu_num),
-            cpufreq::related_cpus(cpu_num),
-            cpufreq::scaling_available_governors(cpu_num),
-            cpufreq::scaling_cur_freq(cpu_num),
-            cpufreq::scaling_driver(cpu_num),
-            cpufreq::scaling_governor(cpu_num),
-            cpufreq::scaling_max_freq(cpu_num),
-            cpufreq::scaling_setspeed(cpu_num),
+            policy,
+            snapshot.affected_cpus,
+            snapshot.bios_limit,
+            snapshot.cpuinfo_cur_freq,
+            snapshot.cpuinfo_max_freq,
+            snapshot.cpuinfo_min_freq,
+            snapshot.cpuinfo_transition_latency,
+            snapshot.related_cpus,
+            snapshot.scaling_available_governors,
+            snapshot.scaling_cur_freq,
+            snapshot.scaling_driver,
+            snapshot.scaling_governor,
+            snapshot.scaling_max_freq,
+            snapshot.scaling_setspeed,
+            snapshot.time_in_state,
         )
     }
 }
@@ -1,21 +1,11 @@
-#[path = "shared/common.rs"]
-mod common;
-
-use common::print_object;
-use sysfs::api::cpu::count_cpus;
+use sysfs::api::cpu;
+use sysfs::api::cpu::amd_pstate::global;
+use sysfs::api::cpu::amd_pstate::PolicySnapshot;
 
 fn main() {
-    for cpu in 0..count_cpus().unwrap() {
-        print_object!(
-            in sysfs::api::cpu::amd_pstate
-            ["/sys/devices/system/cpu/cpufreq/policy{}", cpu] {
-                amd_pstate_highest_perf,
-                amd_pstate_max_freq,
-                amd_pstate_lowest_nonlinear_freq,
-                energy_performance_available_preferences,
-                energy_performance_preference,
-            }
-        );
-        println!();
+    println!("status: {:?}", global::status());
+    println!("prefcore: {:?}", global::prefcore());
+    for policy in cpu::policies().unwrap().into_keys() {
+        println!("{:#?}", PolicySnapshot::read_all(policy));
     }
 }
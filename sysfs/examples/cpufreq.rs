@@ -1,30 +1,8 @@
-#[path = "shared/common.rs"]
-mod common;
-
-use common::print_object;
-use sysfs::api::cpu::count_cpus;
+use sysfs::api::cpu;
+use sysfs::api::cpu::cpufreq::PolicySnapshot;
 
 fn main() {
-    for cpu in 0..count_cpus().unwrap() {
-        print_object!(
-            in sysfs::api::cpu::cpufreq
-            ["/sys/devices/system/cpu/cpufreq/policy{}", cpu] {
-                affected_cpus,
-                bios_limit,
-                cpuinfo_cur_freq,
-                cpuinfo_max_freq,
-                cpuinfo_min_freq,
-                cpuinfo_transition_latency,
-                related_cpus,
-                scaling_available_governors,
-                scaling_cur_freq,
-                scaling_driver,
-                scaling_governor,
-                scaling_max_freq,
-                scaling_min_freq,
-                scaling_setspeed,
-            }
-        );
-        println!();
+    for policy in cpu::policies().unwrap().into_keys() {
+        println!("{:#?}", PolicySnapshot::read_all(policy));
     }
 }
@@ -0,0 +1,32 @@
+use std::time::Duration;
+
+use sysfs::api::cpu;
+use sysfs::api::cpu::cpuidle::{self, IdleStateSnapshot, ResidencySampler};
+
+fn main() {
+    println!("driver: {:?}", cpuidle::global::current_driver());
+    println!("governor: {:?}", cpuidle::global::current_governor());
+    println!(
+        "available governors: {:?}",
+        cpuidle::global::available_governors()
+    );
+
+    let cpus = cpu::online().unwrap();
+    for cpu in cpus.iter() {
+        for state in cpuidle::states(cpu).unwrap() {
+            println!("{:#?}", IdleStateSnapshot::read_all(cpu, state));
+        }
+    }
+
+    let samplers = cpus
+        .iter()
+        .map(|cpu| ResidencySampler::start(cpu).unwrap())
+        .collect::<Vec<_>>();
+    std::thread::sleep(Duration::from_secs(1));
+    for (cpu, sampler) in cpus.iter().zip(samplers) {
+        println!("cpu{cpu}:");
+        for state in sampler.finish().unwrap() {
+            println!("  {:<8} {:.1}", state.name, state.residency);
+        }
+    }
+}
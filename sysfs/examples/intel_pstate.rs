@@ -0,0 +1,13 @@
+use sysfs::api::cpu;
+use sysfs::api::cpu::intel_pstate::global;
+use sysfs::api::cpu::intel_pstate::PolicySnapshot;
+
+fn main() {
+    println!("status: {:?}", global::status());
+    println!("no_turbo: {:?}", global::no_turbo());
+    println!("max_perf_pct: {:?}", global::max_perf_pct());
+    println!("min_perf_pct: {:?}", global::min_perf_pct());
+    for policy in cpu::policies().unwrap().into_keys() {
+        println!("{:#?}", PolicySnapshot::read_all(policy));
+    }
+}
@@ -0,0 +1,14 @@
+use sysfs::api::cpu;
+use sysfs::api::cpu::topology::{self, PolicyTree, TopologySnapshot};
+
+fn main() {
+    for cpu in cpu::present().unwrap().iter() {
+        println!("{:#?}", TopologySnapshot::read_all(cpu));
+    }
+
+    println!("core types: {:?}", topology::core_types());
+
+    let tree = PolicyTree::read().unwrap();
+    println!("hybrid: {}", tree.is_hybrid());
+    println!("{tree:#?}");
+}
@@ -0,0 +1,50 @@
+use crate::lib::{sysfs_attrs, sysfs_path};
+
+pub fn list_block_devices() -> Vec<String> {
+    std::fs::read_dir(sysfs_path("/sys/block"))
+        .map(|iter| {
+            iter.filter_map(Result::ok)
+                .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// <https://www.kernel.org/doc/Documentation/ABI/stable/sysfs-block>
+/// <https://www.kernel.org/doc/html/latest/block/switching-sched.html>
+#[sysfs_attrs(in "/sys/block/{disk}/queue", handle = Queue)]
+pub mod queue {
+    use crate::lib::{sysfs, Selectable, SysfsEnum};
+
+    /// When read, this file will display the current and available IO
+    /// schedulers for this block device. The currently active IO scheduler
+    /// will be enclosed in [] brackets. Writing an IO scheduler name to this
+    /// file will switch control of this block device to that new IO
+    /// scheduler. Note that writing an IO scheduler name to this file will
+    /// attempt to load that IO scheduler module, if it isn't already present
+    /// in the system.
+    ///
+    /// Access: Read, Write
+    #[sysfs]
+    pub fn scheduler(disk: &str) -> Selectable<IoScheduler> {
+        let read = |text: &str| text.parse();
+        // Not validated against the available schedulers, because writing the
+        // name of one that is not loaded will load its module.
+        let write = |scheduler: IoScheduler| scheduler.to_string();
+        ..
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq, SysfsEnum)]
+    #[sysfs_enum(rename_all = "kebab-case")]
+    pub enum IoScheduler {
+        MqDeadline,
+        Kyber,
+        Bfq,
+        /// No scheduling, requests are dispatched in the order that they
+        /// are submitted.
+        #[sysfs_enum(rename = "none")]
+        NoScheduler,
+        #[sysfs_enum(other)]
+        Other(String),
+    }
+}
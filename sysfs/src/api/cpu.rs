@@ -1,30 +1,332 @@
 //! <https://www.kernel.org/doc/html/latest/admin-guide/pm/cpufreq.html?highlight=schedutil#policy-interface-in-sysfs>
-use crate::lib::sysfs_attrs;
-
-pub fn count_cpus() -> crate::Result<usize> {
-    use std::fs::DirEntry;
-    let is_cpu_obj = |inode: &DirEntry| {
-        let name = inode.file_name();
-        let name = name.to_string_lossy();
-        name.starts_with("policy") && name["policy".len()..].chars().all(|ch| ch.is_ascii_digit())
-    };
-    let count = std::fs::read_dir("/sys/devices/system/cpu/cpufreq")?
-        .filter(|res| matches!(res, Ok(inode) if is_cpu_obj(inode)))
-        .count();
-    Ok(count)
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::str::FromStr;
+
+use sysfs_lib::{ParseError, ValidationError};
+
+use crate::lib::{sysfs, sysfs_attrs, sysfs_path, Operation};
+
+/// A set of CPU numbers, written by the kernel in the *cpulist* format,
+/// such as `0-3,8,10-11`. Lists that are separated by spaces, such as
+/// `related_cpus`, are parsed as well.
+///
+/// <https://www.kernel.org/doc/html/latest/admin-guide/cputopology.html>
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CpuSet(BTreeSet<usize>);
+
+impl CpuSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn contains(&self, cpu: usize) -> bool {
+        self.0.contains(&cpu)
+    }
+
+    pub fn insert(&mut self, cpu: usize) -> bool {
+        self.0.insert(cpu)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns the lowest CPU number in the set.
+    pub fn first(&self) -> Option<usize> {
+        self.0.first().copied()
+    }
+
+    /// Returns the CPU numbers in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.0.iter().copied()
+    }
+}
+
+impl FromStr for CpuSet {
+    type Err = ParseError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut set = BTreeSet::new();
+        let items = text
+            .split(|ch: char| ch == ',' || ch.is_ascii_whitespace())
+            .filter(|item| !item.is_empty());
+        for item in items {
+            match item.split_once('-') {
+                Some((first, last)) => {
+                    let (first, last): (usize, usize) = (first.parse()?, last.parse()?);
+                    if first > last {
+                        return Err(ParseError::new(format!("invalid range {item:?}")));
+                    }
+                    set.extend(first..=last);
+                }
+                None => {
+                    set.insert(item.parse()?);
+                }
+            }
+        }
+        Ok(Self(set))
+    }
+}
+
+/// Formats the set in the *cpulist* format, as accepted by the kernel.
+impl fmt::Display for CpuSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut cpus = self.iter().peekable();
+        let mut separator = "";
+        while let Some(first) = cpus.next() {
+            let mut last = first;
+            while let Some(next) = cpus.next_if_eq(&(last + 1)) {
+                last = next;
+            }
+            match first == last {
+                true => write!(f, "{separator}{first}")?,
+                false => write!(f, "{separator}{first}-{last}")?,
+            }
+            separator = ",";
+        }
+        Ok(())
+    }
+}
+
+impl FromIterator<usize> for CpuSet {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl Extend<usize> for CpuSet {
+    fn extend<I: IntoIterator<Item = usize>>(&mut self, iter: I) {
+        self.0.extend(iter)
+    }
+}
+
+impl IntoIterator for CpuSet {
+    type Item = usize;
+    type IntoIter = std::collections::btree_set::IntoIter<usize>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a CpuSet {
+    type Item = usize;
+    type IntoIter = std::iter::Copied<std::collections::btree_set::Iter<'a, usize>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter().copied()
+    }
+}
+
+/// The energy performance preference (EPP) of a policy, a hint to the
+/// hardware about whether to favor performance or energy efficiency. This is
+/// the same interface for the `amd_pstate` and `intel_pstate` drivers.
+///
+/// The named preferences are mapped to raw values by the driver or the
+/// platform firmware. Only `intel_pstate` accepts a raw value from 0
+/// (performance) to 255 (energy efficiency) instead, `amd_pstate` only
+/// accepts the named preferences.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum EnergyPerformancePreference {
+    /// The preference that was set by the platform firmware.
+    Default,
+    Performance,
+    BalancePerformance,
+    BalancePower,
+    Power,
+    Raw(u8),
+    /// A named preference that is not known to this crate.
+    Other(String),
+}
+
+impl EnergyPerformancePreference {
+    pub fn as_raw(&self) -> Option<u8> {
+        match self {
+            Self::Raw(raw) => Some(*raw),
+            _ => None,
+        }
+    }
+
+    /// Rejects named preferences that are not available, and raw values
+    /// unless `scaling_driver` is `intel_pstate` (or `intel_cpufreq`, its
+    /// passive mode), for use in `validate` closures.
+    pub fn validate(
+        &self,
+        available: Option<Vec<Self>>,
+        scaling_driver: &str,
+    ) -> Result<(), ValidationError> {
+        let accepts_raw = matches!(scaling_driver, "intel_pstate" | "intel_cpufreq");
+        match available {
+            _ if self.as_raw().is_some() && !accepts_raw => Err(ValidationError::rejected(
+                format!("raw values are not accepted by {scaling_driver}"),
+            )),
+            _ if self.as_raw().is_some() => Ok(()),
+            Some(available) if !available.contains(self) => {
+                let available = available.iter().map(ToString::to_string);
+                let available = available.collect::<Vec<_>>();
+                Err(ValidationError::rejected(if accepts_raw {
+                    format!("must be one of {available:?} or 0-255")
+                } else {
+                    format!("must be one of {available:?}")
+                }))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl From<&str> for EnergyPerformancePreference {
+    fn from(text: &str) -> Self {
+        match text {
+            "default" => Self::Default,
+            "performance" => Self::Performance,
+            "balance_performance" => Self::BalancePerformance,
+            "balance_power" => Self::BalancePower,
+            "power" => Self::Power,
+            _ => match text.parse() {
+                Ok(raw) => Self::Raw(raw),
+                Err(_) => Self::Other(text.to_owned()),
+            },
+        }
+    }
+}
+
+impl FromStr for EnergyPerformancePreference {
+    type Err = std::convert::Infallible;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Ok(Self::from(text))
+    }
+}
+
+impl fmt::Display for EnergyPerformancePreference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Default => f.write_str("default"),
+            Self::Performance => f.write_str("performance"),
+            Self::BalancePerformance => f.write_str("balance_performance"),
+            Self::BalancePower => f.write_str("balance_power"),
+            Self::Power => f.write_str("power"),
+            Self::Raw(raw) => write!(f, "{raw}"),
+            Self::Other(name) => f.write_str(name),
+        }
+    }
+}
+
+// Serialized as the kernel's spelling, so raw values become strings.
+#[cfg(feature = "serde")]
+impl serde::Serialize for EnergyPerformancePreference {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for EnergyPerformancePreference {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = <String as serde::Deserialize>::deserialize(deserializer)?;
+        Ok(Self::from(text.as_str()))
+    }
+}
+
+/// CPUs that have been allocated resources and can be brought online if
+/// they are present.
+#[sysfs(in "/sys/devices/system/cpu")]
+pub fn possible() -> CpuSet {
+    let read = |text: &str| text.parse();
+    ..
+}
+
+/// CPUs that have been identified as being present in the system.
+#[sysfs(in "/sys/devices/system/cpu")]
+pub fn present() -> CpuSet {
+    let read = |text: &str| text.parse();
+    ..
+}
+
+/// CPUs that are online and being scheduled.
+#[sysfs(in "/sys/devices/system/cpu")]
+pub fn online() -> CpuSet {
+    let read = |text: &str| text.parse();
+    ..
+}
+
+/// CPUs that are not online because they have been hotplugged off, or
+/// exceed the limit of CPUs allowed by the kernel configuration.
+#[sysfs(in "/sys/devices/system/cpu")]
+pub fn offline() -> CpuSet {
+    let read = |text: &str| text.parse();
+    ..
+}
+
+/// Returns the number of every cpufreq policy, with the CPUs that belong to
+/// it (its `related_cpus`). A policy is numbered after the first CPU that
+/// it was created for, so the numbers are not contiguous when policies are
+/// shared by multiple CPUs.
+pub fn policies() -> crate::Result<BTreeMap<usize, CpuSet>> {
+    let path = sysfs_path("/sys/devices/system/cpu/cpufreq");
+    let entries =
+        std::fs::read_dir(&path).map_err(|e| crate::Error::io(&path, Operation::Read, e))?;
+
+    let mut policies = BTreeMap::new();
+    for entry in entries {
+        let entry = entry.map_err(|e| crate::Error::io(&path, Operation::Read, e))?;
+        let policy = entry
+            .file_name()
+            .to_str()
+            .and_then(|name| name.strip_prefix("policy"))
+            .filter(|num| num.chars().all(|ch| ch.is_ascii_digit()))
+            .and_then(|num| num.parse().ok());
+        if let Some(policy) = policy {
+            policies.insert(policy, cpufreq::related_cpus(policy)?);
+        }
+    }
+    Ok(policies)
+}
+
+/// Changes the operation mode of a scaling driver with `set`, and then
+/// returns the policies that exist in the new mode, which are none if the
+/// driver is `disabled` by it.
+fn switch_driver_mode(
+    set: impl FnOnce() -> crate::Result<()>,
+    disabled: bool,
+) -> crate::Result<BTreeMap<usize, CpuSet>> {
+    set()?;
+    if disabled {
+        Ok(BTreeMap::new())
+    } else {
+        policies()
+    }
 }
 
 /// <https://www.kernel.org/doc/html/latest/admin-guide/pm/cpufreq.html#policy-interface-in-sysfs>
-#[sysfs_attrs(in "/sys/devices/system/cpu/cpufreq/policy{cpu}")]
+#[sysfs_attrs(
+    in "/sys/devices/system/cpu/cpufreq/policy{cpu}",
+    handle = Policy,
+    snapshot = PolicySnapshot,
+)]
 pub mod cpufreq {
-    use crate::lib::sysfs;
+    use std::collections::BTreeMap;
+    use std::time::Duration;
+
+    use sysfs_lib::{sysfs_path, sysfs_write, Error, ParseError, ValidationError};
+
+    use super::{CpuSet, EnergyPerformancePreference};
+    use crate::lib::{sysfs, sysfs_attrs};
+    use crate::units::{parse_scaled, Frequency, Percent};
 
     /// List of online CPUs belonging to this policy (i.e. sharing the
     /// hardware performance scaling interface represented by the policyX
     /// policy object).
     #[sysfs]
-    pub fn affected_cpus(cpu: usize) -> Vec<usize> {
-        let read = |text: &str| text.split(' ').map(|int| int.parse().unwrap()).collect();
+    pub fn affected_cpus(cpu: usize) -> CpuSet {
+        let read = |text: &str| text.parse();
         ..
     }
 
@@ -41,9 +343,37 @@ pub mod cpufreq {
     ///
     /// This attribute is not present if the scaling driver in use does not
     /// support it.
-    #[sysfs]
-    pub fn bios_limit(cpu: usize) -> usize {
-        let read = |text: &str| text.parse().unwrap();
+    #[sysfs(optional)]
+    pub fn bios_limit(cpu: usize) -> Frequency {
+        let read = |text: &str| parse_scaled(text, Frequency::checked_from_khz);
+        ..
+    }
+
+    /// Whether the CPUs belonging to this policy are allowed to run at
+    /// boost (turbo) frequencies, above the maximum sustainable frequency.
+    /// Writing 1 or 0 enables or disables boost for this policy only, within
+    /// the limit of the global `boost` attribute.
+    ///
+    /// This attribute is not present if the scaling driver in use does not
+    /// support boost per policy.
+    #[sysfs(optional)]
+    pub fn boost(cpu: usize) -> bool {
+        let read = |text: &str| text.parse::<u8>().map(|int| int == 1);
+        let write = |boost: bool| u8::from(boost).to_string();
+        ..
+    }
+
+    /// An average frequency of all CPUs belonging to this policy (in kHz),
+    /// derived from a hardware provided feedback and reported on a time
+    /// frame spanning at most few milliseconds.
+    ///
+    /// This is expected to be based on the frequency the hardware actually
+    /// runs at and, as such, might require specialised hardware support. If
+    /// one cannot be determined, this attribute should not be present.
+    /// Reading fails with `EAGAIN` for CPUs that remain idle.
+    #[sysfs(optional)]
+    pub fn cpuinfo_avg_freq(cpu: usize) -> Frequency {
+        let read = |text: &str| parse_scaled(text, Frequency::checked_from_khz);
         ..
     }
 
@@ -53,25 +383,25 @@ pub mod cpufreq {
     /// This is expected to be the frequency the hardware actually runs at.
     /// If that frequency cannot be determined, this attribute should not be
     /// present.
-    #[sysfs]
-    pub fn cpuinfo_cur_freq(cpu: usize) -> usize {
-        let read = |text: &str| text.parse().unwrap();
+    #[sysfs(optional)]
+    pub fn cpuinfo_cur_freq(cpu: usize) -> Frequency {
+        let read = |text: &str| parse_scaled(text, Frequency::checked_from_khz);
         ..
     }
 
     /// Maximum possible operating frequency the CPUs belonging to this
     /// policy can run at (in kHz).
     #[sysfs]
-    pub fn cpuinfo_max_freq(cpu: usize) -> usize {
-        let read = |text: &str| text.parse().unwrap();
+    pub fn cpuinfo_max_freq(cpu: usize) -> Frequency {
+        let read = |text: &str| parse_scaled(text, Frequency::checked_from_khz);
         ..
     }
 
     /// Minimum possible operating frequency the CPUs belonging to this
     /// policy can run at (in kHz).
     #[sysfs]
-    pub fn cpuinfo_min_freq(cpu: usize) -> usize {
-        let read = |text: &str| text.parse().unwrap();
+    pub fn cpuinfo_min_freq(cpu: usize) -> Frequency {
+        let read = |text: &str| parse_scaled(text, Frequency::checked_from_khz);
         ..
     }
 
@@ -83,14 +413,66 @@ pub mod cpufreq {
     /// returned by reads from this attribute.
     #[sysfs]
     pub fn cpuinfo_transition_latency(cpu: usize) -> isize {
-        let read = |text: &str| text.parse().unwrap();
+        let read = |text: &str| text.parse();
+        ..
+    }
+
+    /// A list of the energy performance preferences that can be written to
+    /// energy_performance_preference, for any scaling driver that supports
+    /// EPP. This is only present with `amd_pstate` in active mode, or with
+    /// `intel_pstate` if the HWP feature is enabled, and both of those
+    /// modules re-export it.
+    #[sysfs(optional)]
+    pub fn energy_performance_available_preferences(
+        cpu: usize,
+    ) -> Vec<EnergyPerformancePreference> {
+        let read = |text: &str| {
+            text.split_whitespace()
+                .map(EnergyPerformancePreference::from)
+                .collect::<Vec<_>>()
+        };
+        ..
+    }
+
+    /// The current energy performance preference of the policy, for any
+    /// scaling driver that supports EPP. With `intel_pstate`, integer values
+    /// from 0 to 255 can be written as well, and are read back as raw values
+    /// if they do not match one of the named preferences. Other drivers
+    /// refuse them, so they are rejected before writing. Writing is not
+    /// allowed while the performance governor is in use.
+    #[sysfs(optional)]
+    pub fn energy_performance_preference(cpu: usize) -> EnergyPerformancePreference {
+        let read = |text: &str| text.parse();
+        let write = |epp: EnergyPerformancePreference| epp.to_string();
+        let validate = |epp: &EnergyPerformancePreference| {
+            epp.validate(
+                energy_performance_available_preferences(cpu)?,
+                &scaling_driver(cpu)?,
+            )
+        };
         ..
     }
 
     /// List of all (online and offline) CPUs belonging to this policy.
     #[sysfs]
-    pub fn related_cpus(cpu: usize) -> Vec<usize> {
-        let read = |text: &str| text.split(' ').map(|int| int.parse().unwrap()).collect();
+    pub fn related_cpus(cpu: usize) -> CpuSet {
+        let read = |text: &str| text.parse();
+        ..
+    }
+
+    /// List of the frequencies in the frequency table of the scaling driver
+    /// (in kHz), excluding boost frequencies. These are the frequencies that
+    /// scaling_setspeed and the limits will be resolved to.
+    ///
+    /// This attribute is only present if the scaling driver provides a
+    /// frequency table, such as `acpi-cpufreq`.
+    #[sysfs(optional)]
+    pub fn scaling_available_frequencies(cpu: usize) -> Vec<Frequency> {
+        let read = |text: &str| {
+            text.split_whitespace()
+                .map(|freq| parse_scaled(freq, Frequency::checked_from_khz))
+                .collect::<Result<Vec<_>, _>>()
+        };
         ..
     }
 
@@ -104,7 +486,22 @@ pub mod cpufreq {
     /// and be listed by this attribute.]
     #[sysfs]
     pub fn scaling_available_governors(cpu: usize) -> Vec<String> {
-        let read = |text: &str| text.split(' ').map(str::to_owned).collect();
+        let read = |text: &str| text.split(' ').map(str::to_owned).collect::<Vec<_>>();
+        ..
+    }
+
+    /// List of the boost frequencies in the frequency table of the scaling
+    /// driver (in kHz), which can only be used while boost is enabled.
+    ///
+    /// This attribute is only present if the frequency table has boost
+    /// frequencies.
+    #[sysfs(optional)]
+    pub fn scaling_boost_frequencies(cpu: usize) -> Vec<Frequency> {
+        let read = |text: &str| {
+            text.split_whitespace()
+                .map(|freq| parse_scaled(freq, Frequency::checked_from_khz))
+                .collect::<Result<Vec<_>, _>>()
+        };
         ..
     }
 
@@ -122,8 +519,8 @@ pub mod cpufreq {
     /// attribute, but that still may not be the exact current CPU frequency
     /// as seen by the hardware at the moment.
     #[sysfs]
-    pub fn scaling_cur_freq(cpu: usize) -> usize {
-        let read = |text: &str| text.parse().unwrap();
+    pub fn scaling_cur_freq(cpu: usize) -> Frequency {
+        let read = |text: &str| parse_scaled(text, Frequency::checked_from_khz);
         ..
     }
 
@@ -148,6 +545,16 @@ pub mod cpufreq {
     pub fn scaling_governor(cpu: usize) -> String {
         let read = str::to_owned;
         let write = |gov: &str| gov.to_owned();
+        let validate = |gov: &&str| {
+            let available = scaling_available_governors(cpu)?;
+            if available.iter().any(|avail| avail == gov) {
+                Ok(())
+            } else {
+                Err(ValidationError::rejected(format!(
+                    "must be one of {available:?}"
+                )))
+            }
+        };
         ..
     }
 
@@ -158,9 +565,19 @@ pub mod cpufreq {
     /// integer to it will cause a new limit to be set (it must not be lower
     /// than the value of the scaling_min_freq attribute).
     #[sysfs]
-    pub fn scaling_max_freq(cpu: usize) -> usize {
-        let read = |text: &str| text.parse().unwrap();
-        let write = |khz: usize| khz.to_string();
+    pub fn scaling_max_freq(cpu: usize) -> Frequency {
+        let read = |text: &str| parse_scaled(text, Frequency::checked_from_khz);
+        let write = |freq: Frequency| freq.as_khz().to_string();
+        let validate = |freq: &Frequency| {
+            let min = scaling_min_freq(cpu)?;
+            if *freq < min {
+                Err(ValidationError::rejected(format!(
+                    "must not be lower than scaling_min_freq ({min})"
+                )))
+            } else {
+                Ok(())
+            }
+        };
         ..
     }
 
@@ -171,9 +588,19 @@ pub mod cpufreq {
     /// non-negative integer to it will cause a new limit to be set (it must
     /// not be higher than the value of the scaling_max_freq attribute).
     #[sysfs]
-    pub fn scaling_min_freq(cpu: usize) -> usize {
-        let read = |text: &str| text.parse().unwrap();
-        let write = |khz: usize| khz.to_string();
+    pub fn scaling_min_freq(cpu: usize) -> Frequency {
+        let read = |text: &str| parse_scaled(text, Frequency::checked_from_khz);
+        let write = |freq: Frequency| freq.as_khz().to_string();
+        let validate = |freq: &Frequency| {
+            let max = scaling_max_freq(cpu)?;
+            if *freq > max {
+                Err(ValidationError::rejected(format!(
+                    "must not be higher than scaling_max_freq ({max})"
+                )))
+            } else {
+                Ok(())
+            }
+        };
         ..
     }
 
@@ -183,11 +610,318 @@ pub mod cpufreq {
     /// It returns the last frequency requested by the governor (in kHz) or
     /// can be written to in order to set a new frequency for the policy.
     #[sysfs]
-    pub fn scaling_setspeed(cpu: usize) -> usize {
-        let read = |text: &str| text.parse().unwrap();
-        let write = |khz: usize| khz.to_string();
+    pub fn scaling_setspeed(cpu: usize) -> Frequency {
+        let read = |text: &str| parse_scaled(text, Frequency::checked_from_khz);
+        let write = |freq: Frequency| freq.as_khz().to_string();
+        ..
+    }
+
+    /// Returns the frequency in the frequency table of this policy that is
+    /// closest to `freq`, preferring the lower one when two are equally
+    /// close. Boost frequencies are only considered while boost is enabled.
+    ///
+    /// If the scaling driver does not provide a frequency table, `freq` is
+    /// returned unchanged, because the driver will resolve it.
+    pub fn nearest_available_frequency(cpu: usize, freq: Frequency) -> crate::Result<Frequency> {
+        let Some(mut available) = scaling_available_frequencies(cpu)? else {
+            return Ok(freq);
+        };
+        if super::boost::enabled()? == Some(true) {
+            available.extend(scaling_boost_frequencies(cpu)?.unwrap_or_default());
+        }
+        let nearest = available
+            .into_iter()
+            .min_by_key(|available| (available.as_hz().abs_diff(freq.as_hz()), *available));
+        Ok(nearest.unwrap_or(freq))
+    }
+
+    /// A limit for [`set_frequency_range`].
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum FrequencyLimit {
+        Frequency(Frequency),
+        /// A percentage of cpuinfo_max_freq, like the `intel_pstate` limits.
+        Percent(Percent),
+    }
+
+    impl From<Frequency> for FrequencyLimit {
+        fn from(freq: Frequency) -> Self {
+            Self::Frequency(freq)
+        }
+    }
+
+    impl From<Percent> for FrequencyLimit {
+        fn from(percent: Percent) -> Self {
+            Self::Percent(percent)
+        }
+    }
+
+    /// Sets both scaling_min_freq and scaling_max_freq, and returns the
+    /// limits that were written.
+    ///
+    /// The limits are clamped to cpuinfo_min_freq and cpuinfo_max_freq. The
+    /// kernel rejects a minimum above the current maximum (and the reverse),
+    /// so the current maximum is read to decide which limit to write first.
+    pub fn set_frequency_range(
+        cpu: usize,
+        min: impl Into<FrequencyLimit>,
+        max: impl Into<FrequencyLimit>,
+    ) -> crate::Result<(Frequency, Frequency)> {
+        let hw_min = cpuinfo_min_freq(cpu)?;
+        let hw_max = cpuinfo_max_freq(cpu)?;
+        let resolve = |limit: FrequencyLimit| {
+            let freq = match limit {
+                FrequencyLimit::Frequency(freq) => freq,
+                FrequencyLimit::Percent(percent) => Frequency::from_hz(
+                    (hw_max.as_hz() as f64 * f64::from(percent.value()) / 100.0).round() as u64,
+                ),
+            };
+            freq.clamp(hw_min, hw_max)
+        };
+        let (min, max) = (resolve(min.into()), resolve(max.into()));
+
+        if min > max {
+            // Formatted like the generated setter would have written it.
+            return Err(Error::Rejected {
+                path: sysfs_path(format!("{}/scaling_min_freq", Policy::new(cpu))),
+                value: min.as_khz().to_string(),
+                reason: format!("must not be higher than the maximum ({max})"),
+            });
+        }
+
+        if min > scaling_max_freq(cpu)? {
+            set_scaling_max_freq(cpu, max)?;
+            set_scaling_min_freq(cpu, min)?;
+        } else {
+            set_scaling_min_freq(cpu, min)?;
+            set_scaling_max_freq(cpu, max)?;
+        }
+        Ok((min, max))
+    }
+
+    // <https://www.kernel.org/doc/html/latest/cpu-freq/cpufreq-stats.html>
+    //
+    // The statistics are only present if the kernel was built with
+    // `CONFIG_CPU_FREQ_STAT`.
+
+    /// The amount of time spent at each of the frequencies supported by the
+    /// CPUs belonging to this policy, since boot or since the statistics
+    /// were last reset.
+    #[sysfs(in "./stats", optional)]
+    pub fn time_in_state(cpu: usize) -> BTreeMap<Frequency, Duration> {
+        let read = |text: &str| {
+            text.lines()
+                .map(|line| match line.split_once(' ') {
+                    Some((freq, time)) => Ok((
+                        parse_scaled(freq, Frequency::checked_from_khz)?,
+                        // The time is in units of 10 ms (`USER_HZ`).
+                        parse_scaled(time, |ticks: u64| {
+                            ticks.checked_mul(10).map(Duration::from_millis)
+                        })?,
+                    )),
+                    None => Err(ParseError::new(format!("invalid line {line:?}"))),
+                })
+                .collect::<Result<_, ParseError>>()
+        };
         ..
     }
+
+    /// The total number of frequency transitions of the CPUs belonging to
+    /// this policy.
+    #[sysfs(in "./stats", optional)]
+    pub fn total_trans(cpu: usize) -> u64 {
+        let read = |text: &str| text.parse();
+        ..
+    }
+
+    /// The number of transitions between each pair of frequencies. Reading
+    /// this fails with `EFBIG` if the table is larger than a page.
+    #[sysfs(in "./stats", optional)]
+    pub fn trans_table(cpu: usize) -> TransitionTable {
+        let read = |text: &str| text.parse();
+        ..
+    }
+
+    /// A matrix where the entry at row `i` and column `j` is the number of
+    /// transitions from `frequencies[i]` to `frequencies[j]`. The
+    /// frequencies are in the order that the driver provided them, which
+    /// may not be sorted.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub struct TransitionTable {
+        pub frequencies: Vec<Frequency>,
+        pub transitions: Vec<Vec<u64>>,
+    }
+
+    impl TransitionTable {
+        /// Returns the number of transitions from one frequency to another,
+        /// or `None` if either is not in the table.
+        pub fn get(&self, from: Frequency, to: Frequency) -> Option<u64> {
+            let row = self.frequencies.iter().position(|&freq| freq == from)?;
+            let column = self.frequencies.iter().position(|&freq| freq == to)?;
+            Some(self.transitions[row][column])
+        }
+    }
+
+    impl std::str::FromStr for TransitionTable {
+        type Err = ParseError;
+
+        // The first two lines are the headers, where the second is the
+        // frequencies of the columns:
+        //
+        //    From  :    To
+        //          :   3600000   3400000
+        //   3600000:         0         5
+        //   3400000:         4         0
+        fn from_str(text: &str) -> Result<Self, Self::Err> {
+            let mut lines = text.lines().skip(1);
+            let header = lines
+                .next()
+                .ok_or_else(|| ParseError::new("missing header"))?;
+            let frequencies = header
+                .trim_start()
+                .trim_start_matches(':')
+                .split_whitespace()
+                .map(|freq| parse_scaled(freq, Frequency::checked_from_khz))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let mut transitions = Vec::with_capacity(frequencies.len());
+            for (line, &freq) in lines.zip(&frequencies) {
+                let Some((row_freq, counts)) = line.split_once(':') else {
+                    return Err(ParseError::new(format!("invalid line {line:?}")));
+                };
+                if parse_scaled(row_freq, Frequency::checked_from_khz)? != freq {
+                    return Err(ParseError::new(format!("rows out of order at {line:?}")));
+                }
+                let counts = counts
+                    .split_whitespace()
+                    .map(str::parse)
+                    .collect::<Result<Vec<_>, _>>()?;
+                if counts.len() != frequencies.len() {
+                    return Err(ParseError::new(format!(
+                        "wrong number of columns in {line:?}"
+                    )));
+                }
+                transitions.push(counts);
+            }
+            if transitions.len() != frequencies.len() {
+                return Err(ParseError::new("wrong number of rows"));
+            }
+
+            Ok(Self {
+                frequencies,
+                transitions,
+            })
+        }
+    }
+
+    /// Resets the statistics of this policy, which is useful for evaluating
+    /// a configuration without rebooting.
+    ///
+    /// The `reset` attribute is write-only and ignores the value that is
+    /// written, so this is not generated by `sysfs`, which would require one.
+    pub fn reset_stats(cpu: usize) -> crate::Result<()> {
+        sysfs_write(&format!("{}/stats/reset", Policy::new(cpu)), "1")
+    }
+
+    /// <https://www.kernel.org/doc/html/latest/admin-guide/pm/cpufreq.html#frequency-boost-support>
+    ///
+    /// These attributes affect every policy, regardless of the scaling
+    /// driver.
+    #[sysfs_attrs(in "/sys/devices/system/cpu/cpufreq")]
+    pub mod global {
+        use crate::lib::sysfs;
+
+        /// Whether boost (turbo) frequencies are allowed for every policy.
+        /// Writing 1 or 0 enables or disables boost.
+        ///
+        /// This attribute is only present if the scaling driver supports
+        /// boost. The `intel_pstate` driver does not, and has `no_turbo`
+        /// instead.
+        #[sysfs(optional)]
+        pub fn boost() -> bool {
+            let read = |text: &str| text.parse::<u8>().map(|int| int == 1);
+            let write = |boost: bool| u8::from(boost).to_string();
+            ..
+        }
+    }
+}
+
+/// Controls boost (turbo) frequencies through whichever attribute the
+/// scaling driver in use provides for it.
+pub mod boost {
+    use sysfs_lib::{sysfs_exists, sysfs_path, Error, Operation};
+
+    use super::{cpufreq, intel_pstate, policies};
+
+    /// The attribute that boost is controlled by.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Knob {
+        /// `/sys/devices/system/cpu/cpufreq/boost`, used by `acpi-cpufreq`
+        /// and `amd_pstate`.
+        Global,
+        /// `/sys/devices/system/cpu/intel_pstate/no_turbo`, which is the
+        /// inverse of boost.
+        NoTurbo,
+        /// `/sys/devices/system/cpu/cpufreq/policyN/boost`, for drivers that
+        /// only support boost per policy.
+        PerPolicy,
+    }
+
+    /// Returns the attribute that controls boost, or `None` if boost is not
+    /// supported.
+    pub fn detect() -> crate::Result<Option<Knob>> {
+        if cpufreq::global::exists_boost() {
+            return Ok(Some(Knob::Global));
+        }
+        if sysfs_exists("/sys/devices/system/cpu/intel_pstate/no_turbo") {
+            return Ok(Some(Knob::NoTurbo));
+        }
+        let policies = policies()?;
+        if !policies.is_empty() && policies.keys().all(|&policy| cpufreq::exists_boost(policy)) {
+            return Ok(Some(Knob::PerPolicy));
+        }
+        Ok(None)
+    }
+
+    pub fn is_supported() -> crate::Result<bool> {
+        detect().map(|knob| knob.is_some())
+    }
+
+    /// Returns whether boost is enabled, or `None` if boost is not
+    /// supported. When boost is controlled per policy, it is enabled if any
+    /// policy has it enabled.
+    pub fn enabled() -> crate::Result<Option<bool>> {
+        match detect()? {
+            Some(Knob::Global) => cpufreq::global::boost(),
+            Some(Knob::NoTurbo) => intel_pstate::global::no_turbo().map(|no_turbo| Some(!no_turbo)),
+            Some(Knob::PerPolicy) => {
+                for policy in policies()?.into_keys() {
+                    if cpufreq::boost(policy)? == Some(true) {
+                        return Ok(Some(true));
+                    }
+                }
+                Ok(Some(false))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Enables or disables boost for every policy. If boost is not supported,
+    /// this returns [`Error::MissingAttribute`] for the global `boost`
+    /// attribute.
+    pub fn set_enabled(enabled: bool) -> crate::Result<()> {
+        match detect()? {
+            Some(Knob::Global) => cpufreq::global::set_boost(enabled),
+            Some(Knob::NoTurbo) => intel_pstate::global::set_no_turbo(!enabled),
+            Some(Knob::PerPolicy) => policies()?
+                .into_keys()
+                .try_for_each(|policy| cpufreq::set_boost(policy, enabled)),
+            None => Err(Error::MissingAttribute {
+                path: sysfs_path("/sys/devices/system/cpu/cpufreq/boost"),
+                op: Operation::Write(u8::from(enabled).to_string()),
+            }),
+        }
+    }
 }
 
 // Currently the functions in here are all prefixed with `amd_pstate`.
@@ -199,9 +933,17 @@ pub mod cpufreq {
 //
 // Because they are now in a separate module, I think it is best to remove the
 // prefix.
-#[sysfs_attrs(in "/sys/devices/system/cpu/cpufreq/policy{cpu}")]
+#[sysfs_attrs(
+    in "/sys/devices/system/cpu/cpufreq/policy{cpu}",
+    handle = Policy,
+    snapshot = PolicySnapshot,
+)]
 pub mod amd_pstate {
-    use crate::lib::sysfs;
+    use sysfs_lib::ParseError;
+
+    use super::policies;
+    use crate::lib::{sysfs, sysfs_attrs};
+    use crate::units::{parse_scaled, Frequency};
 
     /// Maximum CPPC performance and CPU frequency that the driver is allowed to
     /// set, in percent of the maximum supported CPPC performance level (the
@@ -212,14 +954,14 @@ pub mod amd_pstate {
     /// cpuinfo. This attribute is read-only.
     #[sysfs]
     pub fn amd_pstate_highest_perf(cpu: usize) -> usize {
-        let read = |text: &str| text.parse().unwrap();
+        let read = |text: &str| text.parse();
         ..
     }
 
     /// See documentation for [`amd_pstate_highest_perf`].
     #[sysfs]
-    pub fn amd_pstate_max_freq(cpu: usize) -> usize {
-        let read = |text: &str| text.parse().unwrap();
+    pub fn amd_pstate_max_freq(cpu: usize) -> Frequency {
+        let read = |text: &str| parse_scaled(text, Frequency::checked_from_khz);
         ..
     }
 
@@ -228,74 +970,379 @@ pub mod amd_pstate {
     /// (Please see the lowest non-linear performance in AMD CPPC Performance
     /// Capability.) This attribute is read-only.
     #[sysfs]
-    pub fn amd_pstate_lowest_nonlinear_freq(cpu: usize) -> usize {
-        let read = |text: &str| text.parse().unwrap();
+    pub fn amd_pstate_lowest_nonlinear_freq(cpu: usize) -> Frequency {
+        let read = |text: &str| parse_scaled(text, Frequency::checked_from_khz);
         ..
     }
 
-    /// A list of all the supported EPP preferences that could be used for
-    /// energy_performance_preference on this system. These profiles represent
-    /// different hints that are provided to the low-level firmware about the
-    /// user's desired energy vs efficiency tradeoff. default represents the epp
-    /// value is set by platform firmware. This attribute is read-only.
-    #[sysfs]
-    pub fn energy_performance_available_preferences(cpu: usize) -> Vec<String> {
-        let read = |text: &str| text.split(' ').map(str::to_owned).collect();
+    /// Whether the platform supports the preferred core feature and it has
+    /// been enabled. This attribute is read-only.
+    ///
+    /// This attribute is not present on kernels without preferred core
+    /// support.
+    #[sysfs(optional)]
+    pub fn amd_pstate_hw_prefcore(cpu: usize) -> bool {
+        let read = |text: &str| match text {
+            "enabled" => Ok(true),
+            "disabled" => Ok(false),
+            _ => Err(ParseError::new(format!("unknown state {text:?}"))),
+        };
         ..
     }
 
-    /// The current energy performance preference can be read from this
-    /// attribute. and user can change current preference according to energy or
-    /// performance needs Please get all support profiles list from
-    /// energy_performance_available_preferences attribute, all the profiles are
-    /// integer values defined between 0 to 255 when EPP feature is enabled by
-    /// platform firmware, if EPP feature is disabled, driver will ignore the
-    /// written value This attribute is read-write.
-    #[sysfs]
-    pub fn energy_performance_preference(cpu: usize) -> String {
-        let read = str::to_owned;
-        let write = |epp: &str| epp.to_owned();
+    /// The performance ranking of the core. This number doesn't have any
+    /// unit, but larger numbers are preferred at the time of reading. This
+    /// can change at runtime based on platform conditions. This attribute is
+    /// read-only.
+    ///
+    /// This attribute is not present on kernels without preferred core
+    /// support.
+    #[sysfs(optional)]
+    pub fn amd_pstate_prefcore_ranking(cpu: usize) -> u32 {
+        let read = |text: &str| text.parse();
         ..
     }
+
+    /// Returns every CPU with the ranking of its policy, from the most
+    /// preferred to the least preferred, or `None` if the rankings are not
+    /// present. CPUs that are ranked the same are sorted by number.
+    ///
+    /// Rankings can change at runtime, so this is only accurate at the time
+    /// of reading.
+    pub fn cpus_by_ranking() -> crate::Result<Option<Vec<(usize, u32)>>> {
+        let mut ranked = Vec::new();
+        for (policy, cpus) in policies()? {
+            let Some(ranking) = amd_pstate_prefcore_ranking(policy)? else {
+                return Ok(None);
+            };
+            ranked.extend(cpus.iter().map(|cpu| (cpu, ranking)));
+        }
+        ranked.sort_by(|(cpu_a, ranking_a), (cpu_b, ranking_b)| {
+            ranking_b.cmp(ranking_a).then(cpu_a.cmp(cpu_b))
+        });
+        Ok(Some(ranked))
+    }
+
+    pub use super::cpufreq::{
+        energy_performance_available_preferences, energy_performance_preference,
+        exists_energy_performance_available_preferences, exists_energy_performance_preference,
+        set_energy_performance_preference, set_energy_performance_preference_verified,
+    };
+
+    /// <https://www.kernel.org/doc/html/latest/admin-guide/pm/amd-pstate.html#global-attributes>
+    ///
+    /// These attributes affect every policy. Changing the operation mode
+    /// re-registers the driver, which removes and creates the per-policy
+    /// attributes, so use [`global::switch_mode`] to also find the policies
+    /// that exist afterward.
+    #[sysfs_attrs(in "/sys/devices/system/cpu/amd_pstate")]
+    pub mod global {
+        use std::collections::BTreeMap;
+
+        use sysfs_lib::ParseError;
+
+        use crate::api::cpu::{switch_driver_mode, CpuSet};
+        use crate::lib::{sysfs, SysfsEnum};
+
+        /// Operation mode of the driver. This attribute can be written to
+        /// in order to change the driver's operation mode or to unregister
+        /// it. The string written to it must be one of the possible values
+        /// of it and, if successful, writing one of these values to the
+        /// sysfs file will cause the driver to switch over to the operation
+        /// mode represented by that string - or to be unregistered in the
+        /// "disable" case.
+        #[sysfs]
+        pub fn status() -> Mode {
+            let read = |text: &str| text.parse();
+            let write = |mode: Mode| mode.to_string();
+            ..
+        }
+
+        #[derive(Clone, Debug, PartialEq, Eq, SysfsEnum)]
+        #[sysfs_enum(rename_all = "lowercase")]
+        pub enum Mode {
+            /// The driver is functional and in the active mode, where the
+            /// firmware selects performance levels from the energy
+            /// performance preference.
+            Active,
+            /// The driver is functional and in the passive mode, where the
+            /// governor requests performance levels.
+            Passive,
+            /// The driver is functional and in the guided mode, where the
+            /// governor requests a range that the firmware selects from.
+            Guided,
+            /// The driver is unregistered and not functional now.
+            Disable,
+            #[sysfs_enum(other)]
+            Other(String),
+        }
+
+        /// Preferred core state of the driver, where "enabled" means that
+        /// the scheduler favors the cores that have a higher ranking. This
+        /// attribute is read-only to check the state of preferred core set
+        /// by the kernel parameter.
+        #[sysfs(optional)]
+        pub fn prefcore() -> bool {
+            let read = |text: &str| match text {
+                "enabled" => Ok(true),
+                "disabled" => Ok(false),
+                _ => Err(ParseError::new(format!("unknown state {text:?}"))),
+            };
+            ..
+        }
+
+        /// Changes the operation mode, and then returns the policies that
+        /// exist in the new mode, as by [`policies`](crate::api::cpu::policies).
+        /// Handles to policies that were made before should not be used,
+        /// because the attributes that they had may not exist anymore.
+        pub fn switch_mode(mode: Mode) -> crate::Result<BTreeMap<usize, CpuSet>> {
+            let disabled = mode == Mode::Disable;
+            switch_driver_mode(|| set_status_verified(mode).map(drop), disabled)
+        }
+    }
+}
+
+/// <https://www.kernel.org/doc/html/latest/admin-guide/pm/intel_pstate.html>
+///
+/// Like `amd_pstate`, the per-policy attributes of this driver are in the
+/// `cpufreq` policy directories, along with the generic ones.
+#[sysfs_attrs(
+    in "/sys/devices/system/cpu/cpufreq/policy{cpu}",
+    handle = Policy,
+    snapshot = PolicySnapshot,
+)]
+pub mod intel_pstate {
+    use crate::lib::{sysfs, sysfs_attrs};
+    use crate::units::{parse_scaled, Frequency};
+
+    /// Shows the base frequency of the CPU. Any frequency above this will be
+    /// in the turbo frequency range.
+    ///
+    /// This attribute is only present if the HWP feature is enabled.
+    #[sysfs(optional)]
+    pub fn base_frequency(cpu: usize) -> Frequency {
+        let read = |text: &str| parse_scaled(text, Frequency::checked_from_khz);
+        ..
+    }
+
+    pub use super::cpufreq::{
+        energy_performance_available_preferences, energy_performance_preference,
+        exists_energy_performance_available_preferences, exists_energy_performance_preference,
+        set_energy_performance_preference, set_energy_performance_preference_verified,
+    };
+
+    /// <https://www.kernel.org/doc/html/latest/admin-guide/pm/intel_pstate.html#global-attributes>
+    ///
+    /// These attributes affect every policy. As with `amd_pstate`, changing
+    /// the operation mode re-registers the driver, so use
+    /// [`global::switch_mode`] to also find the policies that exist
+    /// afterward.
+    #[sysfs_attrs(in "/sys/devices/system/cpu/intel_pstate")]
+    pub mod global {
+        use std::collections::BTreeMap;
+
+        use sysfs_lib::{validate_range, ValidationError};
+
+        use crate::api::cpu::{switch_driver_mode, CpuSet};
+        use crate::lib::{sysfs, SysfsEnum};
+        use crate::units::Percent;
+
+        /// Operation mode of the driver. This attribute can be written to
+        /// in order to change the driver's operation mode or to unregister
+        /// it.
+        #[sysfs]
+        pub fn status() -> Mode {
+            let read = |text: &str| text.parse();
+            let write = |mode: Mode| mode.to_string();
+            ..
+        }
+
+        #[derive(Clone, Debug, PartialEq, Eq, SysfsEnum)]
+        #[sysfs_enum(rename_all = "lowercase")]
+        pub enum Mode {
+            /// The driver is functional and in the active mode, where it
+            /// selects P-states itself, or the hardware does with HWP.
+            Active,
+            /// The driver is functional and in the passive mode, where the
+            /// governor requests P-states.
+            Passive,
+            /// The driver is not functional (it is not registered as a
+            /// scaling driver with the CPUFreq core).
+            Off,
+            #[sysfs_enum(other)]
+            Other(String),
+        }
+
+        /// If set, the driver is not allowed to set any turbo P-states. If
+        /// unset (the default), turbo P-states can be set by the driver.
+        ///
+        /// This replaces the general `boost` attribute of other drivers. It
+        /// does not affect the maximum supported frequency value exposed
+        /// via the policy interface, but it affects the maximum possible
+        /// value of per-policy P-state limits.
+        #[sysfs]
+        pub fn no_turbo() -> bool {
+            let read = |text: &str| text.parse::<u8>().map(|int| int == 1);
+            let write = |no_turbo: bool| u8::from(no_turbo).to_string();
+            ..
+        }
+
+        /// Maximum P-state the driver is allowed to set in percent of the
+        /// maximum supported performance level (the highest supported turbo
+        /// P-state).
+        ///
+        /// This attribute is not present if the
+        /// `intel_pstate=per_cpu_perf_limits` argument is present in the
+        /// kernel command line.
+        #[sysfs(optional)]
+        pub fn max_perf_pct() -> Percent {
+            let read = |text: &str| text.parse().map(Percent::new);
+            let write = |percent: Percent| (percent.round().value() as u8).to_string();
+            let validate = |percent: &Percent| {
+                let percent = percent.round();
+                validate_range(&percent, Percent::ZERO..=Percent::FULL)?;
+                match min_perf_pct()? {
+                    Some(min) if percent < min => Err(ValidationError::rejected(format!(
+                        "must not be lower than min_perf_pct ({min})"
+                    ))),
+                    _ => Ok(()),
+                }
+            };
+            ..
+        }
+
+        /// Minimum P-state the driver is allowed to set in percent of the
+        /// maximum supported performance level (the highest supported turbo
+        /// P-state).
+        ///
+        /// This attribute is not present if the
+        /// `intel_pstate=per_cpu_perf_limits` argument is present in the
+        /// kernel command line.
+        #[sysfs(optional)]
+        pub fn min_perf_pct() -> Percent {
+            let read = |text: &str| text.parse().map(Percent::new);
+            let write = |percent: Percent| (percent.round().value() as u8).to_string();
+            let validate = |percent: &Percent| {
+                let percent = percent.round();
+                validate_range(&percent, Percent::ZERO..=Percent::FULL)?;
+                match max_perf_pct()? {
+                    Some(max) if percent > max => Err(ValidationError::rejected(format!(
+                        "must not be higher than max_perf_pct ({max})"
+                    ))),
+                    _ => Ok(()),
+                }
+            };
+            ..
+        }
+
+        /// If set, causes the minimum P-state limit to be increased
+        /// dynamically for a short time whenever a task previously waiting
+        /// on I/O is selected to run on a given logical CPU. If unset (the
+        /// default), this is disabled.
+        ///
+        /// This attribute is only present if the driver works in the active
+        /// mode with the HWP feature enabled in the processor.
+        #[sysfs(optional)]
+        pub fn hwp_dynamic_boost() -> bool {
+            let read = |text: &str| text.parse::<u8>().map(|int| int == 1);
+            let write = |boost: bool| u8::from(boost).to_string();
+            ..
+        }
+
+        /// Ratio of the turbo range size to the size of the entire range of
+        /// supported P-states, in percent. This attribute is read-only.
+        ///
+        /// This attribute is present only if the value exposed by it is the
+        /// same for all of the CPUs in the system.
+        #[sysfs(optional)]
+        pub fn turbo_pct() -> Percent {
+            let read = |text: &str| text.parse().map(Percent::new);
+            ..
+        }
+
+        /// Number of P-states supported by the processor (between 0 and 255
+        /// inclusive) including both turbo and non-turbo P-states. The value
+        /// is not affected by the no_turbo setting. This attribute is
+        /// read-only.
+        ///
+        /// This attribute is present only if the value exposed by it is the
+        /// same for all of the CPUs in the system.
+        #[sysfs(optional)]
+        pub fn num_pstates() -> u8 {
+            let read = |text: &str| text.parse();
+            ..
+        }
+
+        /// If set, enables the energy-efficiency optimizations, which may
+        /// limit the maximum operating frequency. With HWP enabled, the
+        /// optimizations are done only in the turbo frequency range. Without
+        /// it, they are done in the entire available frequency range. By
+        /// default, they are disabled if HWP is enabled.
+        ///
+        /// This attribute is only present on platforms with CPUs matching
+        /// the Kaby Lake or Coffee Lake desktop CPU model.
+        #[sysfs(optional)]
+        pub fn energy_efficiency() -> bool {
+            let read = |text: &str| text.parse::<u8>().map(|int| int == 1);
+            let write = |enabled: bool| u8::from(enabled).to_string();
+            ..
+        }
+
+        /// Changes the operation mode, and then returns the policies that
+        /// exist in the new mode, as by [`policies`](crate::api::cpu::policies).
+        /// Handles to policies that were made before should not be used,
+        /// because the attributes that they had may not exist anymore.
+        pub fn switch_mode(mode: Mode) -> crate::Result<BTreeMap<usize, CpuSet>> {
+            let disabled = mode == Mode::Off;
+            switch_driver_mode(|| set_status_verified(mode).map(drop), disabled)
+        }
+    }
 }
 
 /// <https://www.kernel.org/doc/html/latest/admin-guide/acpi/cppc_sysfs.html>
-#[sysfs_attrs(in "/sys/devices/system/cpu/cpu{cpu}/acpi_cppc")]
+#[sysfs_attrs(
+    in "/sys/devices/system/cpu/cpu{cpu}/acpi_cppc",
+    handle = Cppc,
+    snapshot = CppcSnapshot,
+)]
 pub mod acpi_cppc {
+    use sysfs_lib::ParseError;
+
     use crate::lib::sysfs;
+    use crate::units::{parse_scaled, Frequency};
 
     /// Highest performance of this processor (abstract scale).
     #[sysfs]
     pub fn highest_perf(cpu: usize) -> usize {
-        let read = |text: &str| text.parse().unwrap();
+        let read = |text: &str| text.parse();
         ..
     }
 
     /// Highest sustained performance of this processor (abstract scale).
     #[sysfs]
     pub fn nominal_perf(cpu: usize) -> usize {
-        let read = |text: &str| text.parse().unwrap();
+        let read = |text: &str| text.parse();
         ..
     }
 
     /// Lowest performance of this processor with nonlinear power savings (abstract scale).
     #[sysfs]
     pub fn lowest_nonlinear_perf(cpu: usize) -> usize {
-        let read = |text: &str| text.parse().unwrap();
+        let read = |text: &str| text.parse();
         ..
     }
 
     /// Lowest performance of this processor (abstract scale).
     #[sysfs]
     pub fn lowest_perf(cpu: usize) -> usize {
-        let read = |text: &str| text.parse().unwrap();
+        let read = |text: &str| text.parse();
         ..
     }
 
     /// CPU frequency corresponding to lowest_perf (in MHz).
-    #[sysfs]
-    pub fn lowest_freq(cpu: usize) -> usize {
-        let read = |text: &str| text.parse().unwrap();
+    #[sysfs(optional)]
+    pub fn lowest_freq(cpu: usize) -> Frequency {
+        let read = |text: &str| parse_scaled(text, Frequency::checked_from_mhz);
         ..
     }
 
@@ -303,9 +1350,9 @@ pub mod acpi_cppc {
     /// frequencies should only be used to report processor performance in
     /// frequency instead of abstract scale. These values should not be used
     /// for any functional decisions.
-    #[sysfs]
-    pub fn nominal_freq(cpu: usize) -> usize {
-        let read = |text: &str| text.parse().unwrap();
+    #[sysfs(optional)]
+    pub fn nominal_freq(cpu: usize) -> Frequency {
+        let read = |text: &str| parse_scaled(text, Frequency::checked_from_mhz);
         ..
     }
 
@@ -315,36 +1362,37 @@ pub mod acpi_cppc {
     /// performance.
     #[sysfs]
     pub fn feedback_ctrs(cpu: usize) -> FeedbackCounters {
-        let read = |text: &str| text.parse().unwrap();
+        let read = |text: &str| text.parse();
         ..
     }
 
     #[derive(Copy, Clone, Debug)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub struct FeedbackCounters {
         pub reference: usize,
         pub delivered: usize,
     }
 
     impl std::str::FromStr for FeedbackCounters {
-        type Err = crate::Error;
+        type Err = ParseError;
 
-        fn from_str(text: &str) -> crate::Result<Self> {
+        fn from_str(text: &str) -> Result<Self, Self::Err> {
             let mut reference = None;
             let mut delivered = None;
 
             for text in text.split(' ') {
                 if let Some(text) = text.strip_prefix("ref:") {
-                    reference = Some(text.parse().unwrap());
+                    reference = Some(text.parse()?);
                 } else if let Some(text) = text.strip_prefix("del:") {
-                    delivered = Some(text.parse().unwrap());
+                    delivered = Some(text.parse()?);
                 } else {
-                    todo!("parsing error")
+                    return Err(ParseError::new(format!("unknown counter {text:?}")));
                 }
             }
 
             Ok(Self {
-                reference: reference.unwrap(),
-                delivered: delivered.unwrap(),
+                reference: reference.ok_or_else(|| ParseError::new("missing counter \"ref\""))?,
+                delivered: delivered.ok_or_else(|| ParseError::new("missing counter \"del\""))?,
             })
         }
     }
@@ -352,7 +1400,7 @@ pub mod acpi_cppc {
     /// Minimum time for the feedback counters to wraparound (seconds).
     #[sysfs]
     pub fn wraparound_time(cpu: usize) -> usize {
-        let read = |text: &str| text.parse().unwrap();
+        let read = |text: &str| text.parse();
         ..
     }
 
@@ -360,7 +1408,829 @@ pub mod acpi_cppc {
     /// (abstract scale).
     #[sysfs]
     pub fn reference_perf(cpu: usize) -> usize {
-        let read = |text: &str| text.parse().unwrap();
+        let read = |text: &str| text.parse();
+        ..
+    }
+}
+
+/// <https://www.kernel.org/doc/html/latest/admin-guide/pm/cpuidle.html#representation-of-idle-states>
+///
+/// Every CPU has its own copy of each idle state, numbered from the
+/// shallowest (`state0`, usually `POLL`) to the deepest.
+#[sysfs_attrs(
+    in "/sys/devices/system/cpu/cpu{cpu}/cpuidle/state{state}",
+    handle = IdleState,
+    snapshot = IdleStateSnapshot,
+)]
+pub mod cpuidle {
+    use std::time::{Duration, Instant};
+
+    use super::online;
+    use crate::lib::{sysfs, sysfs_attrs, sysfs_path, Operation};
+    use crate::units::Percent;
+
+    /// Returns the number of every idle state of a CPU, in order from the
+    /// shallowest to the deepest.
+    pub fn states(cpu: usize) -> crate::Result<Vec<usize>> {
+        let path = sysfs_path(format!("/sys/devices/system/cpu/cpu{cpu}/cpuidle"));
+        let entries =
+            std::fs::read_dir(&path).map_err(|e| crate::Error::io(&path, Operation::Read, e))?;
+
+        let mut states = Vec::new();
+        for entry in entries {
+            let entry = entry.map_err(|e| crate::Error::io(&path, Operation::Read, e))?;
+            let state: Option<usize> = entry
+                .file_name()
+                .to_str()
+                .and_then(|name| name.strip_prefix("state"))
+                .filter(|num| num.chars().all(|ch| ch.is_ascii_digit()))
+                .and_then(|num| num.parse().ok());
+            states.extend(state);
+        }
+        states.sort_unstable();
+        Ok(states)
+    }
+
+    /// Total number of times this idle state had been asked for, but the
+    /// observed idle duration was certainly too short to match its target
+    /// residency.
+    #[sysfs(optional)]
+    pub fn above(cpu: usize, state: usize) -> u64 {
+        let read = |text: &str| text.parse();
+        ..
+    }
+
+    /// Total number of times this idle state had been asked for, but
+    /// certainly a deeper idle state would have been a better match for the
+    /// observed idle duration.
+    #[sysfs(optional)]
+    pub fn below(cpu: usize, state: usize) -> u64 {
+        let read = |text: &str| text.parse();
+        ..
+    }
+
+    /// Description of the idle state.
+    #[sysfs]
+    pub fn desc(cpu: usize, state: usize) -> String {
+        let read = str::to_owned;
+        ..
+    }
+
+    /// Whether or not this idle state is disabled.
+    ///
+    /// Writing 1 to it causes the idle state to be disabled for this
+    /// particular CPU and writing 0 to it allows the governor to take it
+    /// into consideration for the given CPU and the driver to ask for it
+    /// (unless that state was disabled globally in the driver – in which
+    /// case it cannot be used at all). Disabling an idle state for one CPU
+    /// does not prevent it from being asked for by the other CPUs, see
+    /// [`set_disable_all`].
+    #[sysfs]
+    pub fn disable(cpu: usize, state: usize) -> bool {
+        let read = |text: &str| text.parse::<u8>().map(|int| int == 1);
+        let write = |disable: bool| u8::from(disable).to_string();
+        ..
+    }
+
+    /// Disables or enables an idle state for every online CPU, which is
+    /// needed for it to never be asked for. Stops at the first CPU that
+    /// fails.
+    pub fn set_disable_all(state: usize, disable: bool) -> crate::Result<()> {
+        for cpu in online()? {
+            set_disable(cpu, state, disable)?;
+        }
+        Ok(())
+    }
+
+    /// Exit latency of the idle state (in microseconds).
+    #[sysfs]
+    pub fn latency(cpu: usize, state: usize) -> Duration {
+        let read = |text: &str| text.parse().map(Duration::from_micros);
         ..
     }
+
+    /// Name of the idle state.
+    #[sysfs]
+    pub fn name(cpu: usize, state: usize) -> String {
+        let read = str::to_owned;
+        ..
+    }
+
+    /// Target residency of the idle state (in microseconds).
+    #[sysfs]
+    pub fn residency(cpu: usize, state: usize) -> Duration {
+        let read = |text: &str| text.parse().map(Duration::from_micros);
+        ..
+    }
+
+    /// Total time spent in this idle state by the given CPU (as measured by
+    /// the kernel, in microseconds).
+    #[sysfs]
+    pub fn time(cpu: usize, state: usize) -> Duration {
+        let read = |text: &str| text.parse().map(Duration::from_micros);
+        ..
+    }
+
+    /// Total number of times the hardware has been asked by the given CPU to
+    /// enter this idle state.
+    #[sysfs]
+    pub fn usage(cpu: usize, state: usize) -> u64 {
+        let read = |text: &str| text.parse();
+        ..
+    }
+
+    /// The time that a CPU spent in one of its idle states over an interval,
+    /// as measured by [`ResidencySampler`].
+    #[derive(Clone, Debug, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize))]
+    pub struct StateResidency {
+        pub state: usize,
+        pub name: String,
+        pub time: Duration,
+        /// The share of the interval that was spent in this state.
+        pub residency: Percent,
+    }
+
+    /// Measures the share of an interval that a CPU spent in each of its
+    /// idle states, from the difference in their `time` attributes. The
+    /// remainder is the time that the CPU was not idle.
+    ///
+    /// The kernel only adds to `time` when the CPU leaves the state, so an
+    /// interval that ends while the CPU is idle is under-counted. Short
+    /// intervals are affected the most.
+    ///
+    /// To sample several CPUs over the same interval, start a sampler for
+    /// each of them before waiting.
+    #[derive(Clone, Debug)]
+    pub struct ResidencySampler {
+        cpu: usize,
+        started: Instant,
+        times: Vec<(usize, String, Duration)>,
+    }
+
+    impl ResidencySampler {
+        /// Reads the current time of every idle state of `cpu`.
+        pub fn start(cpu: usize) -> crate::Result<Self> {
+            let times = states(cpu)?
+                .into_iter()
+                .map(|state| Ok((state, name(cpu, state)?, time(cpu, state)?)))
+                .collect::<crate::Result<_>>()?;
+            Ok(Self {
+                cpu,
+                started: Instant::now(),
+                times,
+            })
+        }
+
+        /// Returns the residency of every idle state since
+        /// [`ResidencySampler::start`].
+        pub fn finish(self) -> crate::Result<Vec<StateResidency>> {
+            let elapsed = self.started.elapsed().as_secs_f64();
+            self.times
+                .into_iter()
+                .map(|(state, name, before)| {
+                    let time = time(self.cpu, state)?.saturating_sub(before);
+                    let fraction = if elapsed > 0.0 {
+                        (time.as_secs_f64() / elapsed).min(1.0)
+                    } else {
+                        0.0
+                    };
+                    Ok(StateResidency {
+                        state,
+                        name,
+                        time,
+                        residency: Percent::from_fraction(fraction as f32),
+                    })
+                })
+                .collect()
+        }
+    }
+
+    /// Blocks for `interval`, and returns the residency of every idle state
+    /// of `cpu` over it.
+    pub fn sample_residency(cpu: usize, interval: Duration) -> crate::Result<Vec<StateResidency>> {
+        let sampler = ResidencySampler::start(cpu)?;
+        std::thread::sleep(interval);
+        sampler.finish()
+    }
+
+    /// <https://www.kernel.org/doc/html/latest/admin-guide/pm/cpuidle.html#the-cpuidle-sysfs-interface>
+    ///
+    /// These attributes affect every CPU.
+    #[sysfs_attrs(in "/sys/devices/system/cpu/cpuidle")]
+    pub mod global {
+        use sysfs_lib::ValidationError;
+
+        use crate::lib::sysfs;
+
+        /// The idle governors that can be selected with
+        /// [`current_governor`]. Only present since Linux 5.7 or with the
+        /// `cpuidle_sysfs_switch` kernel parameter.
+        #[sysfs(optional)]
+        pub fn available_governors() -> Vec<String> {
+            let read = |text: &str| {
+                text.split_whitespace()
+                    .map(str::to_owned)
+                    .collect::<Vec<_>>()
+            };
+            ..
+        }
+
+        /// The name of the CPUIdle driver currently in use, or `none` if
+        /// there is no driver.
+        #[sysfs]
+        pub fn current_driver() -> String {
+            let read = str::to_owned;
+            ..
+        }
+
+        /// The name of the idle governor currently in use. Writing the name
+        /// of one of the [`available_governors`] to it switches to that
+        /// governor.
+        #[sysfs]
+        pub fn current_governor() -> String {
+            let read = str::to_owned;
+            let write = |gov: &str| gov.to_owned();
+            let validate = |gov: &&str| match available_governors()? {
+                Some(available) if !available.iter().any(|avail| avail == gov) => Err(
+                    ValidationError::rejected(format!("must be one of {available:?}")),
+                ),
+                _ => Ok(()),
+            };
+            ..
+        }
+    }
+}
+
+/// <https://www.kernel.org/doc/html/latest/admin-guide/cputopology.html>
+///
+/// The IDs are those of the hardware platform rather than the kernel's, so
+/// they are only meaningful for comparing CPUs with each other.
+#[sysfs_attrs(
+    in "/sys/devices/system/cpu/cpu{cpu}/topology",
+    handle = Topology,
+    snapshot = TopologySnapshot,
+)]
+pub mod topology {
+    use std::collections::BTreeMap;
+
+    use super::{policies, present, CpuSet};
+    use crate::lib::sysfs;
+
+    /// Physical package id of cpuX. Typically corresponds to a physical
+    /// socket number, but the actual value is architecture and platform
+    /// dependent.
+    #[sysfs]
+    pub fn physical_package_id(cpu: usize) -> i32 {
+        let read = |text: &str| text.parse();
+        ..
+    }
+
+    /// The CPU die ID of cpuX.
+    #[sysfs(optional)]
+    pub fn die_id(cpu: usize) -> i32 {
+        let read = |text: &str| text.parse();
+        ..
+    }
+
+    /// The cluster ID of cpuX, or -1 if the platform does not describe
+    /// clusters.
+    #[sysfs(optional)]
+    pub fn cluster_id(cpu: usize) -> i32 {
+        let read = |text: &str| text.parse();
+        ..
+    }
+
+    /// The CPU core ID of cpuX.
+    #[sysfs]
+    pub fn core_id(cpu: usize) -> i32 {
+        let read = |text: &str| text.parse();
+        ..
+    }
+
+    /// List of CPUs within the same core, which are its SMT siblings. Only
+    /// present since Linux 5.5, see [`thread_siblings_list`].
+    #[sysfs(optional)]
+    pub fn core_cpus_list(cpu: usize) -> CpuSet {
+        let read = |text: &str| text.parse();
+        ..
+    }
+
+    /// Deprecated name for [`core_cpus_list`], which is present on every
+    /// kernel.
+    #[sysfs]
+    pub fn thread_siblings_list(cpu: usize) -> CpuSet {
+        let read = |text: &str| text.parse();
+        ..
+    }
+
+    /// List of CPUs within the same cluster.
+    #[sysfs(optional)]
+    pub fn cluster_cpus_list(cpu: usize) -> CpuSet {
+        let read = |text: &str| text.parse();
+        ..
+    }
+
+    /// List of CPUs within the same die.
+    #[sysfs(optional)]
+    pub fn die_cpus_list(cpu: usize) -> CpuSet {
+        let read = |text: &str| text.parse();
+        ..
+    }
+
+    /// List of CPUs sharing the same physical_package_id. Only present
+    /// since Linux 5.5, see [`core_siblings_list`].
+    #[sysfs(optional)]
+    pub fn package_cpus_list(cpu: usize) -> CpuSet {
+        let read = |text: &str| text.parse();
+        ..
+    }
+
+    /// Deprecated name for [`package_cpus_list`], which is present on every
+    /// kernel.
+    #[sysfs]
+    pub fn core_siblings_list(cpu: usize) -> CpuSet {
+        let read = |text: &str| text.parse();
+        ..
+    }
+
+    /// The capacity of cpuX relative to the other CPUs, where the most
+    /// capable CPU is 1024. Only present on systems where the CPUs differ
+    /// in capacity, such as big.LITTLE.
+    #[sysfs(in "/sys/devices/system/cpu/cpu{cpu}", optional)]
+    pub fn cpu_capacity(cpu: usize) -> u32 {
+        let read = |text: &str| text.parse();
+        ..
+    }
+
+    /// The kind of a core on a hybrid CPU.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    #[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+    pub enum CoreType {
+        Performance,
+        Efficiency,
+    }
+
+    /// The `cpu_core` PMU, which only exists on Intel hybrid CPUs.
+    pub mod cpu_core {
+        use super::CpuSet;
+        use crate::lib::sysfs;
+
+        /// The CPUs of this PMU, which are the performance cores.
+        #[sysfs(in "/sys/devices/cpu_core", optional)]
+        pub fn cpus() -> CpuSet {
+            let read = |text: &str| text.parse();
+            ..
+        }
+    }
+
+    /// The `cpu_atom` PMU, which only exists on Intel hybrid CPUs.
+    pub mod cpu_atom {
+        use super::CpuSet;
+        use crate::lib::sysfs;
+
+        /// The CPUs of this PMU, which are the efficiency cores.
+        #[sysfs(in "/sys/devices/cpu_atom", optional)]
+        pub fn cpus() -> CpuSet {
+            let read = |text: &str| text.parse();
+            ..
+        }
+    }
+
+    /// Returns the type of every present CPU, or an empty map if the CPUs
+    /// are all of the same type.
+    ///
+    /// The types are taken from the `cpu_core` and `cpu_atom` PMUs on Intel,
+    /// and only one of them is present if one type of core is disabled.
+    /// Otherwise they are taken from [`cpu_capacity`], where the CPUs with
+    /// the highest capacity are performance cores and all others are
+    /// efficiency cores, so a CPU with more than two tiers (such as
+    /// prime, big and little cores) has its middle tier counted as
+    /// efficiency cores.
+    pub fn core_types() -> crate::Result<BTreeMap<usize, CoreType>> {
+        let performance = cpu_core::cpus()?.filter(|cpus| !cpus.is_empty());
+        let efficiency = cpu_atom::cpus()?.filter(|cpus| !cpus.is_empty());
+        match (performance, efficiency) {
+            (Some(performance), Some(efficiency)) => {
+                let types = performance
+                    .iter()
+                    .map(|cpu| (cpu, CoreType::Performance))
+                    .chain(efficiency.iter().map(|cpu| (cpu, CoreType::Efficiency)))
+                    .collect();
+                return Ok(types);
+            }
+            // A hybrid CPU with one type of core disabled, such as the
+            // efficiency cores in the firmware settings.
+            (Some(_), None) | (None, Some(_)) => return Ok(BTreeMap::new()),
+            (None, None) => {}
+        }
+
+        let mut capacities = BTreeMap::new();
+        for cpu in present()? {
+            match cpu_capacity(cpu)? {
+                Some(capacity) => capacities.insert(cpu, capacity),
+                None => return Ok(BTreeMap::new()),
+            };
+        }
+        let highest = capacities.values().copied().max();
+        if capacities
+            .values()
+            .all(|&capacity| Some(capacity) == highest)
+        {
+            return Ok(BTreeMap::new());
+        }
+        let types = capacities
+            .into_iter()
+            .map(|(cpu, capacity)| {
+                if Some(capacity) == highest {
+                    (cpu, CoreType::Performance)
+                } else {
+                    (cpu, CoreType::Efficiency)
+                }
+            })
+            .collect();
+        Ok(types)
+    }
+
+    /// The cpufreq policies, grouped by the type of their CPUs, so that
+    /// different limits can be applied to performance and efficiency cores.
+    /// Each policy is listed with its `related_cpus`, as by [`policies`].
+    #[derive(Clone, Debug, Default, PartialEq, Eq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize))]
+    pub struct PolicyTree {
+        pub performance: BTreeMap<usize, CpuSet>,
+        pub efficiency: BTreeMap<usize, CpuSet>,
+        /// Policies whose CPUs have no known type, which is every policy if
+        /// the CPU is not hybrid.
+        pub unknown: BTreeMap<usize, CpuSet>,
+    }
+
+    impl PolicyTree {
+        pub fn read() -> crate::Result<Self> {
+            let types = core_types()?;
+            let mut tree = Self::default();
+            for (policy, cpus) in policies()? {
+                // Every CPU of a policy shares its hardware, so any of them
+                // can be used.
+                let group = match cpus.iter().find_map(|cpu| types.get(&cpu)) {
+                    Some(CoreType::Performance) => &mut tree.performance,
+                    Some(CoreType::Efficiency) => &mut tree.efficiency,
+                    None => &mut tree.unknown,
+                };
+                group.insert(policy, cpus);
+            }
+            Ok(tree)
+        }
+
+        /// Whether there are policies of both types.
+        pub fn is_hybrid(&self) -> bool {
+            !self.performance.is_empty() && !self.efficiency.is_empty()
+        }
+
+        /// The policies of CPUs of the given type.
+        pub fn policies(&self, core_type: CoreType) -> &BTreeMap<usize, CpuSet> {
+            match core_type {
+                CoreType::Performance => &self.performance,
+                CoreType::Efficiency => &self.efficiency,
+            }
+        }
+    }
+}
+
+/// <https://www.kernel.org/doc/html/latest/admin-guide/pm/cpufreq.html#generic-scaling-governors>
+///
+/// The tunables of a governor are only present while it is in use by at
+/// least one policy. They are shared by every policy, unless the scaling
+/// driver sets `have_governor_per_policy` (as on some big.LITTLE systems), in
+/// which case every policy has its own. That flag is not exposed to user
+/// space, so [`governor_dir`](governor::governor_dir) tells them apart by
+/// which directory exists.
+pub mod governor {
+    use sysfs_lib::sysfs_exists;
+
+    /// Returns the directory of the tunables of `governor` for a policy,
+    /// which is either in the policy directory or in the `cpufreq`
+    /// directory.
+    pub fn governor_dir(cpu: usize, governor: &str) -> String {
+        let per_policy = format!("/sys/devices/system/cpu/cpufreq/policy{cpu}/{governor}");
+        if sysfs_exists(&per_policy) {
+            per_policy
+        } else {
+            format!("/sys/devices/system/cpu/cpufreq/{governor}")
+        }
+    }
+
+    /// <https://www.kernel.org/doc/html/latest/admin-guide/pm/cpufreq.html#schedutil>
+    pub mod schedutil {
+        use std::time::Duration;
+
+        use super::governor_dir;
+        use crate::lib::sysfs;
+
+        /// Minimum time that has to pass between two consecutive runs of
+        /// governor computations (default: 1.5 times the scaling driver's
+        /// transition latency or 1ms if the driver does not provide a
+        /// latency value).
+        ///
+        /// Written in microseconds.
+        #[sysfs(in "{dir}")]
+        pub fn rate_limit_us(cpu: usize) -> Duration {
+            let dir = governor_dir(cpu, "schedutil");
+            let read = |text: &str| text.parse().map(Duration::from_micros);
+            let write = |rate_limit: Duration| rate_limit.as_micros().to_string();
+            ..
+        }
+    }
+
+    /// <https://www.kernel.org/doc/html/latest/admin-guide/pm/cpufreq.html#ondemand>
+    pub mod ondemand {
+        use std::time::Duration;
+
+        use sysfs_lib::validate_range;
+
+        use super::governor_dir;
+        use crate::lib::sysfs;
+        use crate::units::Percent;
+
+        /// How often the governor's worker routine should run.
+        ///
+        /// Written in microseconds.
+        #[sysfs(in "{dir}")]
+        pub fn sampling_rate(cpu: usize) -> Duration {
+            let dir = governor_dir(cpu, "ondemand");
+            let read = |text: &str| text.parse().map(Duration::from_micros);
+            let write = |rate: Duration| rate.as_micros().to_string();
+            ..
+        }
+
+        /// If the estimated CPU load is above this value, the governor will
+        /// set the frequency to the maximum value allowed for the policy.
+        /// Otherwise, the selected frequency will be proportional to the
+        /// estimated CPU load.
+        #[sysfs(in "{dir}")]
+        pub fn up_threshold(cpu: usize) -> Percent {
+            let dir = governor_dir(cpu, "ondemand");
+            let read = |text: &str| text.parse().map(Percent::new);
+            let write = |percent: Percent| (percent.round().value() as u8).to_string();
+            let validate = |percent: &Percent| {
+                validate_range(&percent.round(), Percent::new(1.0)..=Percent::FULL)
+            };
+            ..
+        }
+
+        /// Temporary multiplier to apply to the sampling_rate value if the
+        /// CPU load goes above up_threshold. This causes the governor to
+        /// stay at the maximum frequency for longer.
+        ///
+        /// The kernel documentation gives the maximum as 100, but the
+        /// governor accepts up to 100000.
+        #[sysfs(in "{dir}")]
+        pub fn sampling_down_factor(cpu: usize) -> u32 {
+            let dir = governor_dir(cpu, "ondemand");
+            let read = |text: &str| text.parse();
+            let write = |factor: u32| factor.to_string();
+            let validate = |factor: &u32| validate_range(factor, 1..=100_000);
+            ..
+        }
+
+        /// If set, the CPU load estimation code will treat the CPU time
+        /// spent on executing tasks with "nice" levels greater than 0 as CPU
+        /// idle time.
+        #[sysfs(in "{dir}")]
+        pub fn ignore_nice_load(cpu: usize) -> bool {
+            let dir = governor_dir(cpu, "ondemand");
+            let read = |text: &str| text.parse::<u8>().map(|int| int == 1);
+            let write = |ignore: bool| u8::from(ignore).to_string();
+            ..
+        }
+
+        /// Reduction factor to apply to the original frequency target of
+        /// the governor (including the maximum value used when up_threshold
+        /// is exceeded), in tenths of a percent, between 0 (default) and
+        /// 1000 inclusive.
+        ///
+        /// For example, 100 causes the governor to select 90% of the
+        /// frequency that it would otherwise select.
+        #[sysfs(in "{dir}")]
+        pub fn powersave_bias(cpu: usize) -> u16 {
+            let dir = governor_dir(cpu, "ondemand");
+            let read = |text: &str| text.parse();
+            let write = |bias: u16| bias.to_string();
+            let validate = |bias: &u16| validate_range(bias, 0..=1000);
+            ..
+        }
+    }
+
+    /// <https://www.kernel.org/doc/html/latest/admin-guide/pm/cpufreq.html#conservative>
+    pub mod conservative {
+        use std::time::Duration;
+
+        use sysfs_lib::{validate_range, ValidationError};
+
+        use super::governor_dir;
+        use crate::lib::sysfs;
+        use crate::units::Percent;
+
+        /// How often the governor's worker routine should run.
+        ///
+        /// Written in microseconds.
+        #[sysfs(in "{dir}")]
+        pub fn sampling_rate(cpu: usize) -> Duration {
+            let dir = governor_dir(cpu, "conservative");
+            let read = |text: &str| text.parse().map(Duration::from_micros);
+            let write = |rate: Duration| rate.as_micros().to_string();
+            ..
+        }
+
+        /// If the estimated CPU load is above this value, the frequency will
+        /// go up by freq_step. It must be higher than down_threshold.
+        #[sysfs(in "{dir}")]
+        pub fn up_threshold(cpu: usize) -> Percent {
+            let dir = governor_dir(cpu, "conservative");
+            let read = |text: &str| text.parse().map(Percent::new);
+            let write = |percent: Percent| (percent.round().value() as u8).to_string();
+            let validate = |percent: &Percent| {
+                let percent = percent.round();
+                validate_range(&percent, Percent::ZERO..=Percent::FULL)?;
+                let down_threshold = down_threshold(cpu)?;
+                if percent <= down_threshold {
+                    Err(ValidationError::rejected(format!(
+                        "must be higher than down_threshold ({down_threshold})"
+                    )))
+                } else {
+                    Ok(())
+                }
+            };
+            ..
+        }
+
+        /// Threshold value (20% by default) used to determine the frequency
+        /// change direction. If the estimated CPU load is greater than this
+        /// value, the frequency will go up (by freq_step). If the load is
+        /// less than this value (and the sampling_down_factor mechanism is
+        /// not in effect), the frequency will go down. Otherwise, the
+        /// frequency will not be changed.
+        ///
+        /// It must be at least 1%, and lower than up_threshold.
+        #[sysfs(in "{dir}")]
+        pub fn down_threshold(cpu: usize) -> Percent {
+            let dir = governor_dir(cpu, "conservative");
+            let read = |text: &str| text.parse().map(Percent::new);
+            let write = |percent: Percent| (percent.round().value() as u8).to_string();
+            let validate = |percent: &Percent| {
+                let percent = percent.round();
+                let up_threshold = up_threshold(cpu)?;
+                if percent < Percent::new(1.0) || percent >= up_threshold {
+                    Err(ValidationError::rejected(format!(
+                        "must be at least 1% and lower than up_threshold ({up_threshold})"
+                    )))
+                } else {
+                    Ok(())
+                }
+            };
+            ..
+        }
+
+        /// Frequency decrease deferral factor, between 1 (default) and 10
+        /// inclusive. It effectively causes the frequency to go down
+        /// sampling_down_factor times slower than it ramps up.
+        #[sysfs(in "{dir}")]
+        pub fn sampling_down_factor(cpu: usize) -> u32 {
+            let dir = governor_dir(cpu, "conservative");
+            let read = |text: &str| text.parse();
+            let write = |factor: u32| factor.to_string();
+            let validate = |factor: &u32| validate_range(factor, 1..=10);
+            ..
+        }
+
+        /// If set, the CPU load estimation code will treat the CPU time
+        /// spent on executing tasks with "nice" levels greater than 0 as CPU
+        /// idle time.
+        #[sysfs(in "{dir}")]
+        pub fn ignore_nice_load(cpu: usize) -> bool {
+            let dir = governor_dir(cpu, "conservative");
+            let read = |text: &str| text.parse::<u8>().map(|int| int == 1);
+            let write = |ignore: bool| u8::from(ignore).to_string();
+            ..
+        }
+
+        /// Frequency step in percent of the maximum frequency the governor
+        /// is allowed to set for the policy, between 0 and 100 (5 by
+        /// default). It determines how much the frequency is allowed to
+        /// change in one go. Setting it to 0 will cause the default
+        /// frequency step (5 percent) to be used.
+        #[sysfs(in "{dir}")]
+        pub fn freq_step(cpu: usize) -> Percent {
+            let dir = governor_dir(cpu, "conservative");
+            let read = |text: &str| text.parse().map(Percent::new);
+            let write = |percent: Percent| (percent.round().value() as u8).to_string();
+            let validate =
+                |percent: &Percent| validate_range(&percent.round(), Percent::ZERO..=Percent::FULL);
+            ..
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::units::Frequency;
+
+    #[test]
+    fn cpu_set_ranges() {
+        let cpus: CpuSet = "0-3,8,10-11".parse().unwrap();
+        assert_eq!(cpus.iter().collect::<Vec<_>>(), [0, 1, 2, 3, 8, 10, 11]);
+    }
+
+    #[test]
+    fn cpu_set_space_separated() {
+        let cpus: CpuSet = "4 5 6 7\n".parse().unwrap();
+        assert_eq!(cpus.iter().collect::<Vec<_>>(), [4, 5, 6, 7]);
+    }
+
+    #[test]
+    fn cpu_set_empty() {
+        let cpus: CpuSet = "\n".parse().unwrap();
+        assert!(cpus.is_empty());
+        assert_eq!(cpus.to_string(), "");
+    }
+
+    #[test]
+    fn cpu_set_reversed_range() {
+        assert!("3-0".parse::<CpuSet>().is_err());
+    }
+
+    #[test]
+    fn cpu_set_display_round_trip() {
+        for text in ["0", "0-3,8,10-11", "1,3,5", "0-127"] {
+            assert_eq!(text.parse::<CpuSet>().unwrap().to_string(), text);
+        }
+    }
+
+    #[test]
+    fn epp_named_round_trip() {
+        use EnergyPerformancePreference as Epp;
+        for (text, epp) in [
+            ("default", Epp::Default),
+            ("performance", Epp::Performance),
+            ("balance_performance", Epp::BalancePerformance),
+            ("balance_power", Epp::BalancePower),
+            ("power", Epp::Power),
+            ("turbo", Epp::Other("turbo".to_owned())),
+        ] {
+            assert_eq!(Epp::from(text), epp);
+            assert_eq!(epp.to_string(), text);
+        }
+    }
+
+    #[test]
+    fn epp_raw_round_trip() {
+        for raw in [0, 128, 255] {
+            let epp = EnergyPerformancePreference::from(raw.to_string().as_str());
+            assert_eq!(epp, EnergyPerformancePreference::Raw(raw));
+            assert_eq!(epp.to_string(), raw.to_string());
+        }
+        // Out of range for a raw value, so it is kept as a name.
+        assert_eq!(
+            EnergyPerformancePreference::from("256"),
+            EnergyPerformancePreference::Other("256".to_owned())
+        );
+    }
+
+    #[test]
+    fn epp_raw_only_for_intel_pstate() {
+        let raw = EnergyPerformancePreference::Raw(64);
+        assert!(raw.validate(None, "intel_pstate").is_ok());
+        assert!(raw.validate(None, "intel_cpufreq").is_ok());
+        assert!(raw.validate(None, "amd-pstate-epp").is_err());
+    }
+
+    #[test]
+    fn transition_table() {
+        let text = concat!(
+            "   From  :    To\n",
+            "         :   3600000   3400000\n",
+            "  3600000:         0         5\n",
+            "  3400000:         4         0\n",
+        );
+        let table: cpufreq::TransitionTable = text.parse().unwrap();
+        let (high, low) = (Frequency::from_khz(3600000), Frequency::from_khz(3400000));
+        assert_eq!(table.frequencies, [high, low]);
+        assert_eq!(table.transitions, [[0, 5], [4, 0]]);
+        assert_eq!(table.get(high, low), Some(5));
+        assert_eq!(table.get(low, high), Some(4));
+        assert_eq!(table.get(high, Frequency::from_khz(1)), None);
+    }
+
+    #[test]
+    fn transition_table_wrong_column_count() {
+        let text = concat!(
+            "   From  :    To\n",
+            "         :   3600000   3400000\n",
+            "  3600000:         0         5         1\n",
+            "  3400000:         4         0\n",
+        );
+        assert!(text.parse::<cpufreq::TransitionTable>().is_err());
+    }
 }
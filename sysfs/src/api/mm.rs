@@ -0,0 +1,33 @@
+use crate::lib::sysfs_attrs;
+
+/// <https://www.kernel.org/doc/html/latest/admin-guide/mm/transhuge.html>
+#[sysfs_attrs(in "/sys/kernel/mm/transparent_hugepage")]
+pub mod transparent_hugepage {
+    use crate::lib::{sysfs, Selectable, SysfsEnum};
+
+    /// Transparent Hugepage Support for anonymous memory can be entirely
+    /// disabled (mostly for debugging purposes) or only enabled inside
+    /// `MADV_HUGEPAGE` regions (to avoid the risk of consuming more memory
+    /// resources) or enabled system wide.
+    ///
+    /// Access: Read, Write
+    ///
+    /// Valid values: "always", "madvise", "never"
+    #[sysfs]
+    pub fn enabled() -> Selectable<Enabled> {
+        let read = |text: &str| text.parse();
+        let write = |enabled: Enabled| enabled.to_string();
+        let validate = |enabled: &Enabled| self::enabled()?.validate(enabled);
+        ..
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq, SysfsEnum)]
+    #[sysfs_enum(rename_all = "lowercase")]
+    pub enum Enabled {
+        Always,
+        Madvise,
+        Never,
+        #[sysfs_enum(other)]
+        Other(String),
+    }
+}
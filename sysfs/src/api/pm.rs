@@ -0,0 +1,38 @@
+use crate::lib::sysfs_attrs;
+
+/// <https://www.kernel.org/doc/Documentation/ABI/testing/sysfs-power>
+#[sysfs_attrs(in "/sys/power")]
+pub mod power {
+    use crate::lib::{sysfs, Selectable, SysfsEnum};
+
+    /// Controls the operating mode of system suspend. Reading from it
+    /// returns the available modes, with the mode that will be used on
+    /// subsequent attempts to suspend the system (by writing "mem" to
+    /// `/sys/power/state`) enclosed in square brackets. Writing one of the
+    /// modes causes it to be used on subsequent attempts to suspend.
+    ///
+    /// Access: Read, Write
+    ///
+    /// Valid values: "s2idle" (always present), "shallow" and "deep"
+    /// (present if supported)
+    #[sysfs]
+    pub fn mem_sleep() -> Selectable<MemSleep> {
+        let read = |text: &str| text.parse();
+        let write = |mode: MemSleep| mode.to_string();
+        let validate = |mode: &MemSleep| mem_sleep()?.validate(mode);
+        ..
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq, SysfsEnum)]
+    #[sysfs_enum(rename_all = "lowercase")]
+    pub enum MemSleep {
+        /// Suspend-to-idle.
+        S2Idle,
+        /// Standby, or power-on suspend.
+        Shallow,
+        /// Suspend-to-RAM.
+        Deep,
+        #[sysfs_enum(other)]
+        Other(String),
+    }
+}
@@ -1,7 +1,7 @@
-use crate::lib::sysfs_attrs;
+use crate::lib::{sysfs_attrs, sysfs_path};
 
 pub fn list_power_supplies() -> Vec<String> {
-    std::fs::read_dir("/sys/class/power_supply")
+    std::fs::read_dir(sysfs_path("/sys/class/power_supply"))
         .map(|iter| {
             iter.filter_map(Result::ok)
                 .map(|entry| entry.file_name().to_string_lossy().into_owned())
@@ -12,19 +12,29 @@ pub fn list_power_supplies() -> Vec<String> {
 
 /// <https://www.kernel.org/doc/html/latest/power/power_supply_class.html>
 /// <https://www.kernel.org/doc/Documentation/ABI/testing/sysfs-class-power>
-#[sysfs_attrs(in "/sys/class/power_supply/{psu}")]
+///
+/// Drivers only expose the properties that they support, so every attribute
+/// other than `type` is optional.
+#[sysfs_attrs(
+    in "/sys/class/power_supply/{psu}",
+    handle = PowerSupply,
+    snapshot = PowerSupplySnapshot,
+)]
 pub mod power_supply {
-    use strum::{EnumString, FromRepr, IntoStaticStr};
-    use sysfs_lib::parse_selected;
+    #[cfg(feature = "serde")]
+    use serde::{Deserialize, Serialize};
+    use strum::FromRepr;
+    use sysfs_lib::{validate_range, ParseError, Selectable};
 
-    use crate::lib::sysfs;
+    use crate::lib::{sysfs, SysfsEnum};
+    use crate::units::{parse_scaled, Current, Percent, Power, Temperature, Voltage};
 
     /// Reports the name of the device manufacturer.
     ///
     /// Access: Read
     ///
     /// Valid values: Represented as string
-    #[sysfs]
+    #[sysfs(optional)]
     pub fn manufacturer(psu: &str) -> String {
         let read = str::to_owned;
         ..
@@ -35,7 +45,7 @@ pub mod power_supply {
     /// Access: Read
     ///
     /// Valid values: Represented as string
-    #[sysfs]
+    #[sysfs(optional)]
     pub fn model_name(psu: &str) -> String {
         let read = str::to_owned;
         ..
@@ -46,7 +56,7 @@ pub mod power_supply {
     /// Access: Read
     ///
     /// Valid values: Represented as string
-    #[sysfs]
+    #[sysfs(optional)]
     pub fn serial_number(psu: &str) -> String {
         let read = |text: &str| text.trim().to_owned();
         ..
@@ -59,22 +69,21 @@ pub mod power_supply {
     /// Valid values: "Battery", "UPS", "Mains", "USB", "Wireless"
     #[sysfs]
     pub fn r#type(psu: &str) -> Type {
-        let read = |text: &str| text.parse().unwrap();
+        let read = |text: &str| text.parse();
         ..
     }
 
-    #[derive(Clone, Copy, Debug, IntoStaticStr, EnumString)]
+    #[derive(Clone, Debug, PartialEq, Eq, SysfsEnum)]
     pub enum Type {
-        #[strum(serialize = "Battery")]
         Battery,
-        #[strum(serialize = "UPS")]
+        #[sysfs_enum(rename = "UPS")]
         Ups,
-        #[strum(serialize = "Mains")]
         Mains,
-        #[strum(serialize = "USB")]
+        #[sysfs_enum(rename = "USB")]
         Usb,
-        #[strum(serialize = "Wireless")]
         Wireless,
+        #[sysfs_enum(other)]
+        Other(String),
     }
 
     /// Battery:
@@ -95,9 +104,9 @@ pub mod power_supply {
     /// Valid values: Represented in microamps. Negative values are
     /// used for discharging batteries, positive values for charging
     /// batteries and for USB IBUS current.
-    #[sysfs]
-    pub fn current_avg(psu: &str) -> isize {
-        let read = |text: &str| text.parse().unwrap();
+    #[sysfs(optional)]
+    pub fn current_avg(psu: &str) -> Current {
+        let read = |text: &str| text.parse().map(Current::from_microamps);
         ..
     }
 
@@ -112,9 +121,9 @@ pub mod power_supply {
     /// Access: Read
     ///
     /// Valid values: Represented in microamps
-    #[sysfs]
-    pub fn current_max(psu: &str) -> usize {
-        let read = |text: &str| text.parse().unwrap();
+    #[sysfs(optional)]
+    pub fn current_max(psu: &str) -> Current {
+        let read = |text: &str| text.parse().map(Current::from_microamps);
         ..
     }
 
@@ -137,10 +146,10 @@ pub mod power_supply {
     /// Valid values: Represented in microamps. Negative values are
     /// used for discharging batteries, positive values for charging
     /// batteries and for USB IBUS current.
-    #[sysfs]
-    pub fn current_now(psu: &str) -> isize {
-        let read = |text: &str| text.parse().unwrap();
-        let write = |max: isize| max.to_string();
+    #[sysfs(optional)]
+    pub fn current_now(psu: &str) -> Current {
+        let read = |text: &str| text.parse().map(Current::from_microamps);
+        let write = |current: Current| current.as_microamps().to_string();
         ..
     }
 
@@ -157,9 +166,9 @@ pub mod power_supply {
     /// Access: Read
     ///
     /// Valid values: Represented in 1/10 Degrees Celsius
-    #[sysfs]
-    pub fn temp(psu: &str) -> usize {
-        let read = |text: &str| text.parse().unwrap();
+    #[sysfs(optional)]
+    pub fn temp(psu: &str) -> Temperature {
+        let read = |text: &str| parse_scaled(text, Temperature::checked_from_decidegrees_celsius);
         ..
     }
 
@@ -182,9 +191,9 @@ pub mod power_supply {
     /// Access: Read
     ///
     /// Valid values: Represented in 1/10 Degrees Celsius
-    #[sysfs]
-    pub fn temp_alert_max(psu: &str) -> usize {
-        let read = |text: &str| text.parse().unwrap();
+    #[sysfs(optional)]
+    pub fn temp_alert_max(psu: &str) -> Temperature {
+        let read = |text: &str| parse_scaled(text, Temperature::checked_from_decidegrees_celsius);
         ..
     }
 
@@ -207,9 +216,9 @@ pub mod power_supply {
     /// Access: Read
     ///
     /// Valid values: Represented in 1/10 Degrees Celsius
-    #[sysfs]
-    pub fn temp_alert_min(psu: &str) -> usize {
-        let read = |text: &str| text.parse().unwrap();
+    #[sysfs(optional)]
+    pub fn temp_alert_min(psu: &str) -> Temperature {
+        let read = |text: &str| parse_scaled(text, Temperature::checked_from_decidegrees_celsius);
         ..
     }
 
@@ -225,9 +234,9 @@ pub mod power_supply {
     /// Access: Read
     ///
     /// Valid values: Represented in 1/10 Degrees Celsius
-    #[sysfs]
-    pub fn temp_max(psu: &str) -> usize {
-        let read = |text: &str| text.parse().unwrap();
+    #[sysfs(optional)]
+    pub fn temp_max(psu: &str) -> Temperature {
+        let read = |text: &str| parse_scaled(text, Temperature::checked_from_decidegrees_celsius);
         ..
     }
 
@@ -243,9 +252,9 @@ pub mod power_supply {
     /// Access: Read
     ///
     /// Valid values: Represented in 1/10 Degrees Celsius
-    #[sysfs]
-    pub fn temp_min(psu: &str) -> usize {
-        let read = |text: &str| text.parse().unwrap();
+    #[sysfs(optional)]
+    pub fn temp_min(psu: &str) -> Temperature {
+        let read = |text: &str| parse_scaled(text, Temperature::checked_from_decidegrees_celsius);
         ..
     }
 
@@ -261,17 +270,17 @@ pub mod power_supply {
     /// Access: Read
     ///
     /// Valid values: Represented in microvolts
-    #[sysfs]
-    pub fn voltage_max(psu: &str) -> usize {
-        let read = |text: &str| text.parse().unwrap();
+    #[sysfs(optional)]
+    pub fn voltage_max(psu: &str) -> Voltage {
+        let read = |text: &str| text.parse().map(Voltage::from_microvolts);
         ..
     }
 
     /// Battery:
-
+    ///
     /// Reports the minimum safe VBAT voltage permitted for the
     /// battery, during discharging.
-
+    ///
     /// USB:
     ///
     /// Reports the minimum VBUS voltage the supply can support.
@@ -279,9 +288,9 @@ pub mod power_supply {
     /// Access: Read
     ///
     /// Valid values: Represented in microvolts
-    #[sysfs]
-    pub fn voltage_min(psu: &str) -> usize {
-        let read = |text: &str| text.parse().unwrap();
+    #[sysfs(optional)]
+    pub fn voltage_min(psu: &str) -> Voltage {
+        let read = |text: &str| text.parse().map(Voltage::from_microvolts);
         ..
     }
 
@@ -302,10 +311,10 @@ pub mod power_supply {
     /// Access: Read, Write
     ///
     /// Valid values: Represented in microvolts
-    #[sysfs]
-    pub fn voltage_now(psu: &str) -> usize {
-        let read = |text: &str| text.parse().unwrap();
-        let write = |uvolts: usize| uvolts.to_string();
+    #[sysfs(optional)]
+    pub fn voltage_now(psu: &str) -> Voltage {
+        let read = |text: &str| text.parse().map(Voltage::from_microvolts);
+        let write = |voltage: Voltage| voltage.as_microvolts().to_string();
         ..
     }
 
@@ -314,9 +323,9 @@ pub mod power_supply {
     /// Access: Read
     ///
     /// Valid values: 0 - 100 (percent)
-    #[sysfs]
-    pub fn capacity(psu: &str) -> f32 {
-        let read = |text: &str| text.parse::<f32>().unwrap() / 100.0;
+    #[sysfs(optional)]
+    pub fn capacity(psu: &str) -> Percent {
+        let read = |text: &str| text.parse().map(Percent::new);
         ..
     }
 
@@ -330,10 +339,12 @@ pub mod power_supply {
     /// Access: Read, Write
     ///
     /// Valid values: 0 - 100 (percent)
-    #[sysfs]
-    pub fn capacity_alert_max(psu: &str) -> f32 {
-        let read = |text: &str| text.parse::<f32>().unwrap() / 100.0;
-        let write = |percent: f32| ((percent * 100.0).round() as u8).to_string();
+    #[sysfs(optional)]
+    pub fn capacity_alert_max(psu: &str) -> Percent {
+        let read = |text: &str| text.parse().map(Percent::new);
+        let write = |percent: Percent| (percent.round().value() as u8).to_string();
+        let validate =
+            |percent: &Percent| validate_range(&percent.round(), Percent::ZERO..=Percent::FULL);
         ..
     }
 
@@ -347,10 +358,12 @@ pub mod power_supply {
     /// Access: Read, Write
     ///
     /// Valid values: 0 - 100 (percent)
-    #[sysfs]
-    pub fn capacity_alert_min(psu: &str) -> f32 {
-        let read = |text: &str| text.parse::<f32>().unwrap() / 100.0;
-        let write = |percent: f32| ((percent * 100.0).round() as u8).to_string();
+    #[sysfs(optional)]
+    pub fn capacity_alert_min(psu: &str) -> Percent {
+        let read = |text: &str| text.parse().map(Percent::new);
+        let write = |percent: Percent| (percent.round().value() as u8).to_string();
+        let validate =
+            |percent: &Percent| validate_range(&percent.round(), Percent::ZERO..=Percent::FULL);
         ..
     }
 
@@ -365,9 +378,9 @@ pub mod power_supply {
     /// Access: Read
     ///
     /// Valid values: 0 - 100 (percent)
-    #[sysfs]
-    pub fn capacity_error_margin(psu: &str) -> f32 {
-        let read = |text: &str| text.parse::<f32>().unwrap() / 100.0;
+    #[sysfs(optional)]
+    pub fn capacity_error_margin(psu: &str) -> Percent {
+        let read = |text: &str| text.parse().map(Percent::new);
         ..
     }
 
@@ -376,26 +389,22 @@ pub mod power_supply {
     /// Access: Read
     ///
     /// Valid values: "Unknown", "Critical", "Low", "Normal", "High", "Full"
-    #[sysfs]
+    #[sysfs(optional)]
     pub fn capacity_level(psu: &str) -> CapacityLevel {
-        let read = |text: &str| text.parse().unwrap();
+        let read = |text: &str| text.parse();
         ..
     }
 
-    #[derive(Clone, Copy, Debug, IntoStaticStr, EnumString)]
+    #[derive(Clone, Debug, PartialEq, Eq, SysfsEnum)]
     pub enum CapacityLevel {
-        #[strum(serialize = "Unknown")]
         Unknown,
-        #[strum(serialize = "Critical")]
         Critical,
-        #[strum(serialize = "Low")]
         Low,
-        #[strum(serialize = "Normal")]
         Normal,
-        #[strum(serialize = "High")]
         High,
-        #[strum(serialize = "Full")]
         Full,
+        #[sysfs_enum(other)]
+        Other(String),
     }
 
     /// Maximum allowable charging current. Used for charge rate
@@ -404,10 +413,14 @@ pub mod power_supply {
     /// Access: Read, Write
     ///
     /// Valid values: Represented in microamps
-    #[sysfs]
-    pub fn charge_control_limit(psu: &str) -> usize {
-        let read = |text: &str| text.parse().unwrap();
-        let write = |uamps: usize| uamps.to_string();
+    #[sysfs(optional)]
+    pub fn charge_control_limit(psu: &str) -> Current {
+        let read = |text: &str| text.parse().map(Current::from_microamps);
+        let write = |current: Current| current.as_microamps().to_string();
+        let validate = |current: &Current| match charge_control_limit_max(psu)? {
+            Some(max) => validate_range(current, Current::ZERO..=max),
+            None => Ok(()),
+        };
         ..
     }
 
@@ -416,9 +429,9 @@ pub mod power_supply {
     /// Access: Read
     ///
     /// Valid values: Represented in microamps
-    #[sysfs]
-    pub fn charge_control_limit_max(psu: &str) -> usize {
-        let read = |text: &str| text.parse().unwrap();
+    #[sysfs(optional)]
+    pub fn charge_control_limit_max(psu: &str) -> Current {
+        let read = |text: &str| text.parse().map(Current::from_microamps);
         ..
     }
 
@@ -427,10 +440,12 @@ pub mod power_supply {
     ///
     /// Access: Read, Write
     /// Valid values: 0 - 100 (percent)
-    #[sysfs]
-    pub fn charge_control_start_threshold(psu: &str) -> f32 {
-        let read = |text: &str| text.parse::<f32>().unwrap() / 100.0;
-        let write = |percent: f32| ((percent * 100.0).round() as u8).to_string();
+    #[sysfs(optional)]
+    pub fn charge_control_start_threshold(psu: &str) -> Percent {
+        let read = |text: &str| text.parse().map(Percent::new);
+        let write = |percent: Percent| (percent.round().value() as u8).to_string();
+        let validate =
+            |percent: &Percent| validate_range(&percent.round(), Percent::ZERO..=Percent::FULL);
         ..
     }
 
@@ -443,10 +458,12 @@ pub mod power_supply {
     /// Access: Read, Write
     ///
     /// Valid values: 0 - 100 (percent)
-    #[sysfs]
-    pub fn charge_control_end_threshold(psu: &str) -> f32 {
-        let read = |text: &str| text.parse::<f32>().unwrap() / 100.0;
-        let write = |percent: f32| ((percent * 100.0).round() as u8).to_string();
+    #[sysfs(optional)]
+    pub fn charge_control_end_threshold(psu: &str) -> Percent {
+        let read = |text: &str| text.parse().map(Percent::new);
+        let write = |percent: Percent| (percent.round().value() as u8).to_string();
+        let validate =
+            |percent: &Percent| validate_range(&percent.round(), Percent::ZERO..=Percent::FULL);
         ..
     }
 
@@ -467,33 +484,28 @@ pub mod power_supply {
     /// Valid values:
     /// "Unknown", "N/A", "Trickle", "Fast", "Standard",
     /// "Adaptive", "Custom", "Long Life", "Bypass"
-    #[sysfs]
+    #[sysfs(optional)]
     pub fn charge_type(psu: &str) -> ChargeType {
-        let read = |text: &str| text.parse().unwrap();
-        let write = |charge_type: ChargeType| <&'static str>::from(charge_type).to_owned();
+        let read = |text: &str| text.parse();
+        let write = |charge_type: ChargeType| charge_type.to_string();
         ..
     }
 
-    #[derive(Copy, Clone, Debug, EnumString, IntoStaticStr)]
+    #[derive(Clone, Debug, PartialEq, Eq, SysfsEnum)]
     pub enum ChargeType {
-        #[strum(serialize = "Unknown")]
         Unknown,
-        #[strum(serialize = "N/A")]
+        #[sysfs_enum(rename = "N/A")]
         NotAvailable,
-        #[strum(serialize = "Trickle")]
         Trickle,
-        #[strum(serialize = "Fast")]
         Fast,
-        #[strum(serialize = "Standard")]
         Standard,
-        #[strum(serialize = "Adaptive")]
         Adaptive,
-        #[strum(serialize = "Custom")]
         Custom,
-        #[strum(serialize = "Long Life")]
+        #[sysfs_enum(rename = "Long Life")]
         LongLife,
-        #[strum(serialize = "Bypass")]
         Bypass,
+        #[sysfs_enum(other)]
+        Other(String),
     }
 
     /// Reports the charging current value which is used to determine
@@ -502,9 +514,9 @@ pub mod power_supply {
     /// Access: Read
     ///
     /// Valid values: Represented in microamps
-    #[sysfs]
-    pub fn charge_term_current(psu: &str) -> usize {
-        let read = |text: &str| text.parse().unwrap();
+    #[sysfs(optional)]
+    pub fn charge_term_current(psu: &str) -> Current {
+        let read = |text: &str| text.parse().map(Current::from_microamps);
         ..
     }
 
@@ -519,44 +531,38 @@ pub mod power_supply {
     /// "Watchdog timer expire", "Safety timer expire",
     /// "Over current", "Calibration required", "Warm",
     /// "Cool", "Hot", "No battery"
-    #[sysfs]
+    #[sysfs(optional)]
     pub fn health(psu: &str) -> Health {
-        let read = |text: &str| text.parse().unwrap();
+        let read = |text: &str| text.parse();
         ..
     }
 
-    #[derive(Copy, Clone, Debug, EnumString, IntoStaticStr)]
+    #[derive(Clone, Debug, PartialEq, Eq, SysfsEnum)]
     pub enum Health {
-        #[strum(serialize = "Unknown")]
         Unknown,
-        #[strum(serialize = "Good")]
         Good,
-        #[strum(serialize = "Overheat")]
         Overheat,
-        #[strum(serialize = "Dead")]
         Dead,
-        #[strum(serialize = "Over voltage")]
+        #[sysfs_enum(rename = "Over voltage")]
         OverVoltage,
-        #[strum(serialize = "Unspecified failure")]
+        #[sysfs_enum(rename = "Unspecified failure")]
         UnspecifiedFailure,
-        #[strum(serialize = "cold")]
         Cold,
-        #[strum(serialize = "Watchdog timer expire")]
+        #[sysfs_enum(rename = "Watchdog timer expire")]
         WatchdogTimerExpire,
-        #[strum(serialize = "Safety timer expire")]
+        #[sysfs_enum(rename = "Safety timer expire")]
         SafetyTimerExpire,
-        #[strum(serialize = "Over current")]
+        #[sysfs_enum(rename = "Over current")]
         OverCurrent,
-        #[strum(serialize = "Calibration required")]
+        #[sysfs_enum(rename = "Calibration required")]
         CalibrationRequired,
-        #[strum(serialize = "Warm")]
         Warm,
-        #[strum(serialize = "Cool")]
         Cool,
-        #[strum(serialize = "Hot")]
         Hot,
-        #[strum(serialize = "No battery")]
+        #[sysfs_enum(rename = "No battery")]
         NoBattery,
+        #[sysfs_enum(other)]
+        Other(String),
     }
 
     /// Reports the charging current applied during pre-charging phase
@@ -565,9 +571,9 @@ pub mod power_supply {
     /// Access: Read
     ///
     /// Valid values: Represented in microamps
-    #[sysfs]
-    pub fn precharge_current(psu: &str) -> usize {
-        let read = |text: &str| text.parse().unwrap();
+    #[sysfs(optional)]
+    pub fn precharge_current(psu: &str) -> Current {
+        let read = |text: &str| text.parse().map(Current::from_microamps);
         ..
     }
 
@@ -577,9 +583,9 @@ pub mod power_supply {
     /// Access: Read
     ///
     /// Valid values: 0 (Absent), 1 (Present)
-    #[sysfs]
+    #[sysfs(optional)]
     pub fn present(psu: &str) -> bool {
-        let read = |text: &str| 1 == text.parse::<u8>().unwrap();
+        let read = |text: &str| text.parse::<u8>().map(|int| int == 1);
         ..
     }
 
@@ -591,25 +597,23 @@ pub mod power_supply {
     ///
     /// Valid values:
     /// "Unknown", "Charging", "Discharging", "Not charging", "Full"
-    #[sysfs]
+    #[sysfs(optional)]
     pub fn status(psu: &str) -> Status {
-        let read = |text: &str| text.parse().unwrap();
-        let write = |status: Status| <&'static str>::from(status).to_owned();
+        let read = |text: &str| text.parse();
+        let write = |status: Status| status.to_string();
         ..
     }
 
-    #[derive(Copy, Clone, Debug, EnumString, IntoStaticStr)]
+    #[derive(Clone, Debug, PartialEq, Eq, SysfsEnum)]
     pub enum Status {
-        #[strum(serialize = "Unknown")]
         Unknown,
-        #[strum(serialize = "Charging")]
         Charging,
-        #[strum(serialize = "Discharging")]
         Discharging,
-        #[strum(serialize = "Not charging")]
+        #[sysfs_enum(rename = "Not charging")]
         NotCharging,
-        #[strum(serialize = "Full")]
         Full,
+        #[sysfs_enum(other)]
+        Other(String),
     }
 
     /// Represents the charging behaviour.
@@ -623,18 +627,25 @@ pub mod power_supply {
     /// | `auto`            | Charge normally, respect thresholds      |
     /// | `inhibit-charge`  | Do not charge while AC is attached       |
     /// | `force-discharge` | Force discharge while AC is attached     |
-    #[sysfs]
-    pub fn charge_behaviour(psu: &str) -> ChargeBehaviour {
-        let read = |text: &str| parse_selected(text).unwrap().parse().unwrap();
+    #[sysfs(optional)]
+    pub fn charge_behaviour(psu: &str) -> Selectable<ChargeBehaviour> {
+        let read = |text: &str| text.parse();
+        let write = |behaviour: ChargeBehaviour| behaviour.to_string();
+        let validate = |behaviour: &ChargeBehaviour| match charge_behaviour(psu)? {
+            Some(behaviours) => behaviours.validate(behaviour),
+            None => Ok(()),
+        };
         ..
     }
 
-    #[derive(Copy, Clone, Debug, EnumString, IntoStaticStr)]
-    #[strum(serialize_all = "kebab-case")]
+    #[derive(Clone, Debug, PartialEq, Eq, SysfsEnum)]
+    #[sysfs_enum(rename_all = "kebab-case")]
     pub enum ChargeBehaviour {
         Auto,
         InhibitCharge,
         ForceDischarge,
+        #[sysfs_enum(other)]
+        Other(String),
     }
 
     /// Describes the battery technology supported by the supply.
@@ -643,28 +654,26 @@ pub mod power_supply {
     ///
     /// Valid values:
     /// "Unknown", "NiMH", "Li-ion", "Li-poly", "LiFe", "NiCd", "LiMn"
-    #[sysfs]
+    #[sysfs(optional)]
     pub fn technology(psu: &str) -> Technology {
-        let read = |text: &str| text.parse().unwrap();
+        let read = |text: &str| text.parse();
         ..
     }
 
-    #[derive(Copy, Clone, Debug, EnumString, IntoStaticStr)]
+    #[derive(Clone, Debug, PartialEq, Eq, SysfsEnum)]
     pub enum Technology {
-        #[strum(serialize = "Unknown")]
         Unknown,
-        #[strum(serialize = "NiMH")]
+        #[sysfs_enum(rename = "NiMH")]
         NiMh,
-        #[strum(serialize = "Li-ion")]
+        #[sysfs_enum(rename = "Li-ion")]
         LiIon,
-        #[strum(serialize = "Li-poly")]
+        #[sysfs_enum(rename = "Li-poly")]
         LiPoly,
-        #[strum(serialize = "LiFe")]
         LiFe,
-        #[strum(serialize = "NiCd")]
         NiCd,
-        #[strum(serialize = "LiMn")]
         LiMn,
+        #[sysfs_enum(other)]
+        Other(String),
     }
 
     /// Reports an average VBAT voltage reading for the battery, over a
@@ -674,9 +683,9 @@ pub mod power_supply {
     /// Access: Read
     ///
     /// Valid values: Represented in microvolts
-    #[sysfs]
-    pub fn voltage_avg(psu: &str) -> usize {
-        let read = |text: &str| text.parse().unwrap();
+    #[sysfs(optional)]
+    pub fn voltage_avg(psu: &str) -> Voltage {
+        let read = |text: &str| text.parse().map(Voltage::from_microvolts);
         ..
     }
 
@@ -691,9 +700,9 @@ pub mod power_supply {
     /// |---------------|-----------------------------------|
     /// | > 0           | representing full cycles          |
     /// | = 0           | cycle_count info is not available |
-    #[sysfs]
+    #[sysfs(optional)]
     pub fn cycle_count(psu: &str) -> usize {
-        let read = |text: &str| text.parse().unwrap();
+        let read = |text: &str| text.parse();
         ..
     }
 
@@ -707,10 +716,10 @@ pub mod power_supply {
     /// Access: Read, Write
     ///
     /// Valid values: Represented in microamps
-    #[sysfs]
-    pub fn input_current_limit(psu: &str) -> usize {
-        let read = |text: &str| text.parse().unwrap();
-        let write = |uamps: usize| uamps.to_string();
+    #[sysfs(optional)]
+    pub fn input_current_limit(psu: &str) -> Current {
+        let read = |text: &str| text.parse().map(Current::from_microamps);
+        let write = |current: Current| current.as_microamps().to_string();
         ..
     }
     /// This entry configures the incoming VBUS voltage limit currently
@@ -726,10 +735,10 @@ pub mod power_supply {
     /// Access: Read, Write
     ///
     /// Valid values: Represented in microvolts
-    #[sysfs]
-    pub fn input_voltage_limit(psu: &str) -> usize {
-        let read = |text: &str| text.parse().unwrap();
-        let write = |uvolts: usize| uvolts.to_string();
+    #[sysfs(optional)]
+    pub fn input_voltage_limit(psu: &str) -> Voltage {
+        let read = |text: &str| text.parse().map(Voltage::from_microvolts);
+        let write = |voltage: Voltage| voltage.as_microvolts().to_string();
         ..
     }
 
@@ -742,10 +751,10 @@ pub mod power_supply {
     /// Access: Read, Write
     ///
     /// Valid values: Represented in microwatts
-    #[sysfs]
-    pub fn input_power_limit(psu: &str) -> usize {
-        let read = |text: &str| text.parse().unwrap();
-        let write = |uwatts: usize| uwatts.to_string();
+    #[sysfs(optional)]
+    pub fn input_power_limit(psu: &str) -> Power {
+        let read = |text: &str| text.parse().map(Power::from_microwatts);
+        let write = |power: Power| power.as_microwatts().to_string();
         ..
     }
 
@@ -763,9 +772,13 @@ pub mod power_supply {
     /// | 0     | Offline                |                                |
     /// | 1     | Online Fixed           | Fixed Voltage Supply           |
     /// | 2     | Online Programmable    | Programmable Voltage Supply    |
-    #[sysfs]
+    #[sysfs(optional)]
     pub fn online(psu: &str) -> Online {
-        let read = |text: &str| Online::from_repr(text.parse::<u8>().unwrap()).unwrap();
+        let read = |text: &str| match text.parse::<u8>().map(Online::from_repr) {
+            Ok(Some(state)) => Ok(state),
+            Ok(None) => Err(ParseError::new("unknown online state")),
+            Err(e) => Err(ParseError::from(e)),
+        };
         let write = |state: Online| (state as u8).to_string();
         ..
     }
@@ -778,6 +791,24 @@ pub mod power_supply {
         Programmable = 2,
     }
 
+    // The kernel represents this attribute as an integer, so it is
+    // serialized as one.
+    #[cfg(feature = "serde")]
+    impl Serialize for Online {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_u8(*self as u8)
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    impl<'de> Deserialize<'de> for Online {
+        fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let repr = u8::deserialize(deserializer)?;
+            Self::from_repr(repr)
+                .ok_or_else(|| serde::de::Error::custom(format!("unknown online state {repr}")))
+        }
+    }
+
     /// Reports what type of USB connection is currently active for
     /// the supply, for example it can show if USB-PD capable source
     /// is attached.
@@ -787,33 +818,36 @@ pub mod power_supply {
     /// Valid values:
     /// "Unknown", "SDP", "DCP", "CDP", "ACA", "C", "PD",
     /// "PD_DRP", "PD_PPS", "BrickID"
-    #[sysfs]
-    pub fn usb_type(psu: &str) -> UsbType {
-        let read = |text: &str| text.parse().unwrap();
+    ///
+    /// Every type that the supply supports is listed, with the active one
+    /// in brackets, such as `Unknown SDP [DCP] CDP`.
+    #[sysfs(optional)]
+    pub fn usb_type(psu: &str) -> Selectable<UsbType> {
+        let read = |text: &str| text.parse();
         ..
     }
 
-    #[derive(Copy, Clone, Debug, EnumString, IntoStaticStr)]
+    #[derive(Clone, Debug, PartialEq, Eq, SysfsEnum)]
     pub enum UsbType {
-        #[strum(serialize = "Unknown")]
         Unknown,
-        #[strum(serialize = "SDP")]
+        #[sysfs_enum(rename = "SDP")]
         Sdp,
-        #[strum(serialize = "DCP")]
+        #[sysfs_enum(rename = "DCP")]
         Dcp,
-        #[strum(serialize = "CDP")]
+        #[sysfs_enum(rename = "CDP")]
         Cdp,
-        #[strum(serialize = "ACA")]
+        #[sysfs_enum(rename = "ACA")]
         Aca,
-        #[strum(serialize = "C")]
         C,
-        #[strum(serialize = "PD")]
+        #[sysfs_enum(rename = "PD")]
         Pd,
-        #[strum(serialize = "PD_DRP")]
+        #[sysfs_enum(rename = "PD_DRP")]
         PdDrp,
-        #[strum(serialize = "PD_PPS")]
+        #[sysfs_enum(rename = "PD_PPS")]
         PdPps,
-        #[strum(serialize = "BrickID")]
+        #[sysfs_enum(rename = "BrickID")]
         BrickId,
+        #[sysfs_enum(other)]
+        Other(String),
     }
 }
@@ -4,10 +4,15 @@ pub mod lib {
 }
 
 pub mod api {
+    pub mod block;
     pub mod cpu;
+    pub mod mm;
+    pub mod pm;
     pub mod psu;
 }
 
+pub mod units;
+
 /// Stylistic:
 ///
 /// Intended to be used as `sysfs::Error`, not imported.
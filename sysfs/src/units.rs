@@ -0,0 +1,337 @@
+//! Physical quantities that attributes are represented in.
+//!
+//! Each type stores the smallest unit that the kernel uses for it, so that
+//! converting from an attribute never loses precision. The kernel does not
+//! agree on one unit per quantity (cpufreq uses kHz, while ACPI CPPC uses
+//! MHz), so always construct these with the function that names the unit
+//! that the attribute is documented in.
+
+use std::fmt;
+use std::str::FromStr;
+
+use sysfs_lib::ParseError;
+
+/// Writes the value with the largest SI prefix that keeps it at or above 1,
+/// using the precision of the formatter, or two decimal places.
+fn fmt_si(f: &mut fmt::Formatter<'_>, value: f64, unit: &str) -> fmt::Result {
+    const PREFIXES: [(f64, &str); 6] = [
+        (1e9, "G"),
+        (1e6, "M"),
+        (1e3, "k"),
+        (1.0, ""),
+        (1e-3, "m"),
+        (1e-6, "µ"),
+    ];
+    let (scale, prefix) = PREFIXES
+        .into_iter()
+        .find(|(scale, _)| value.abs() >= *scale)
+        .filter(|_| value != 0.0)
+        .unwrap_or((1.0, ""));
+    let precision = f.precision().unwrap_or(2);
+    write!(f, "{:.*} {prefix}{unit}", precision, value / scale)
+}
+
+/// Parses an integer that is in the unit of an attribute, and converts it
+/// with one of the `checked_from_*` constructors, so that a value that is too
+/// large for the type is a [`ParseError`] rather than an overflow.
+pub fn parse_scaled<I, T>(text: &str, from: impl FnOnce(I) -> Option<T>) -> Result<T, ParseError>
+where
+    I: FromStr,
+    ParseError: From<I::Err>,
+{
+    from(text.trim().parse()?)
+        .ok_or_else(|| ParseError::new(format!("{} is out of range", text.trim())))
+}
+
+/// Unwraps the result of a `checked_from_*` constructor, for the
+/// constructors that are given values by the caller rather than the kernel.
+const fn expect_in_range<T: Copy>(value: Option<T>) -> T {
+    match value {
+        Some(value) => value,
+        None => panic!("value is out of range"),
+    }
+}
+
+/// A frequency, stored in hertz. Serialized as hertz.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Frequency(u64);
+
+impl Frequency {
+    pub const fn from_hz(hz: u64) -> Self {
+        Self(hz)
+    }
+
+    /// Panics if the value does not fit, see [`Self::checked_from_khz`].
+    pub const fn from_khz(khz: u64) -> Self {
+        expect_in_range(Self::checked_from_khz(khz))
+    }
+
+    pub const fn checked_from_khz(khz: u64) -> Option<Self> {
+        match khz.checked_mul(1_000) {
+            Some(value) => Some(Self(value)),
+            None => None,
+        }
+    }
+
+    /// Panics if the value does not fit, see [`Self::checked_from_mhz`].
+    pub const fn from_mhz(mhz: u64) -> Self {
+        expect_in_range(Self::checked_from_mhz(mhz))
+    }
+
+    pub const fn checked_from_mhz(mhz: u64) -> Option<Self> {
+        match mhz.checked_mul(1_000_000) {
+            Some(value) => Some(Self(value)),
+            None => None,
+        }
+    }
+
+    pub const fn as_hz(self) -> u64 {
+        self.0
+    }
+
+    /// Truncated to a whole number of kilohertz.
+    pub const fn as_khz(self) -> u64 {
+        self.0 / 1_000
+    }
+
+    /// Truncated to a whole number of megahertz.
+    pub const fn as_mhz(self) -> u64 {
+        self.0 / 1_000_000
+    }
+
+    pub fn as_ghz(self) -> f64 {
+        self.0 as f64 / 1e9
+    }
+}
+
+impl fmt::Display for Frequency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_si(f, self.0 as f64, "Hz")
+    }
+}
+
+/// An electric current, stored in microamps. Negative values are used by
+/// batteries that are discharging. Serialized as microamps.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Current(i64);
+
+impl Current {
+    pub const ZERO: Self = Self(0);
+
+    pub const fn from_microamps(microamps: i64) -> Self {
+        Self(microamps)
+    }
+
+    /// Panics if the value does not fit, see [`Self::checked_from_milliamps`].
+    pub const fn from_milliamps(milliamps: i64) -> Self {
+        expect_in_range(Self::checked_from_milliamps(milliamps))
+    }
+
+    pub const fn checked_from_milliamps(milliamps: i64) -> Option<Self> {
+        match milliamps.checked_mul(1_000) {
+            Some(value) => Some(Self(value)),
+            None => None,
+        }
+    }
+
+    pub const fn as_microamps(self) -> i64 {
+        self.0
+    }
+
+    pub fn as_amps(self) -> f64 {
+        self.0 as f64 / 1e6
+    }
+}
+
+impl fmt::Display for Current {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_si(f, self.as_amps(), "A")
+    }
+}
+
+/// An electric potential, stored in microvolts. Serialized as microvolts.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Voltage(u64);
+
+impl Voltage {
+    pub const fn from_microvolts(microvolts: u64) -> Self {
+        Self(microvolts)
+    }
+
+    /// Panics if the value does not fit, see [`Self::checked_from_millivolts`].
+    pub const fn from_millivolts(millivolts: u64) -> Self {
+        expect_in_range(Self::checked_from_millivolts(millivolts))
+    }
+
+    pub const fn checked_from_millivolts(millivolts: u64) -> Option<Self> {
+        match millivolts.checked_mul(1_000) {
+            Some(value) => Some(Self(value)),
+            None => None,
+        }
+    }
+
+    pub const fn as_microvolts(self) -> u64 {
+        self.0
+    }
+
+    pub fn as_volts(self) -> f64 {
+        self.0 as f64 / 1e6
+    }
+}
+
+impl fmt::Display for Voltage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_si(f, self.as_volts(), "V")
+    }
+}
+
+/// A rate of energy transfer, stored in microwatts. Negative values are
+/// used by batteries that are discharging. Serialized as microwatts.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Power(i64);
+
+impl Power {
+    pub const fn from_microwatts(microwatts: i64) -> Self {
+        Self(microwatts)
+    }
+
+    /// Panics if the value does not fit, see [`Self::checked_from_milliwatts`].
+    pub const fn from_milliwatts(milliwatts: i64) -> Self {
+        expect_in_range(Self::checked_from_milliwatts(milliwatts))
+    }
+
+    pub const fn checked_from_milliwatts(milliwatts: i64) -> Option<Self> {
+        match milliwatts.checked_mul(1_000) {
+            Some(value) => Some(Self(value)),
+            None => None,
+        }
+    }
+
+    pub const fn as_microwatts(self) -> i64 {
+        self.0
+    }
+
+    pub fn as_watts(self) -> f64 {
+        self.0 as f64 / 1e6
+    }
+}
+
+impl fmt::Display for Power {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_si(f, self.as_watts(), "W")
+    }
+}
+
+/// A temperature, stored in millidegrees Celsius. Serialized as
+/// millidegrees Celsius.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Temperature(i64);
+
+impl Temperature {
+    pub const fn from_millidegrees_celsius(millidegrees: i64) -> Self {
+        Self(millidegrees)
+    }
+
+    /// For attributes that are in tenths of a degree, such as those of
+    /// `power_supply`. Panics if the value does not fit, see
+    /// [`Self::checked_from_decidegrees_celsius`].
+    pub const fn from_decidegrees_celsius(decidegrees: i64) -> Self {
+        expect_in_range(Self::checked_from_decidegrees_celsius(decidegrees))
+    }
+
+    pub const fn checked_from_decidegrees_celsius(decidegrees: i64) -> Option<Self> {
+        match decidegrees.checked_mul(100) {
+            Some(value) => Some(Self(value)),
+            None => None,
+        }
+    }
+
+    pub const fn as_millidegrees_celsius(self) -> i64 {
+        self.0
+    }
+
+    /// Truncated to a whole number of tenths of a degree.
+    pub const fn as_decidegrees_celsius(self) -> i64 {
+        self.0 / 100
+    }
+
+    pub fn as_celsius(self) -> f64 {
+        self.0 as f64 / 1e3
+    }
+}
+
+impl fmt::Display for Temperature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let precision = f.precision().unwrap_or(1);
+        write!(f, "{:.*} °C", precision, self.as_celsius())
+    }
+}
+
+/// A percentage, stored as a number from 0 to 100 rather than a fraction.
+/// Serialized as that number.
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Percent(f32);
+
+impl Percent {
+    pub const ZERO: Self = Self(0.0);
+    pub const FULL: Self = Self(100.0);
+
+    pub const fn new(percent: f32) -> Self {
+        Self(percent)
+    }
+
+    pub fn from_fraction(fraction: f32) -> Self {
+        Self(fraction * 100.0)
+    }
+
+    pub const fn value(self) -> f32 {
+        self.0
+    }
+
+    pub fn as_fraction(self) -> f32 {
+        self.0 / 100.0
+    }
+
+    /// Rounds to a whole percent, which is what attributes that take an
+    /// integer are given. Validate this rather than the original value, so
+    /// that the value that is checked is the one that is written.
+    pub fn round(self) -> Self {
+        Self(self.0.round())
+    }
+}
+
+impl fmt::Display for Percent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let precision = f.precision().unwrap_or(0);
+        write!(f, "{:.*}%", precision, self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_scaled_in_range() {
+        assert_eq!(
+            parse_scaled("3600000\n", Frequency::checked_from_khz),
+            Ok(Frequency::from_mhz(3600))
+        );
+        assert_eq!(
+            parse_scaled("-52", Temperature::checked_from_decidegrees_celsius),
+            Ok(Temperature::from_millidegrees_celsius(-5200))
+        );
+    }
+
+    #[test]
+    fn parse_scaled_overflow() {
+        assert!(parse_scaled(&u64::MAX.to_string(), Frequency::checked_from_khz).is_err());
+        assert!(parse_scaled(&i64::MIN.to_string(), Current::checked_from_milliamps).is_err());
+    }
+}
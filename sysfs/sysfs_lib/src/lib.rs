@@ -1,14 +1,17 @@
 // <https://www.kernel.org/doc/html/latest/filesystems/sysfs.html>
 // <https://www.kernel.org/doc/html/latest/admin-guide/sysfs-rules.html>
 //
-// If you see unchecked string functions being called,
-// it's because *sysfs* is guaranteed to be ASCII (where we expect text).
-
-// The maximum number of bytes that can be read from any given
-// *sysfs* attribute. Generally there should be nothing larger than this.
+// Text in *sysfs* is expected to be ASCII, but some attributes are copied
+// verbatim from firmware (such as `serial_number` or `manufacturer`), so
+// nothing here assumes that it is. Use `sysfs_read_bytes` for attributes
+// that are binary.
 
+use std::fmt::Display;
 use std::fs::OpenOptions;
 use std::io::{ErrorKind, Read as _, Write as _};
+use std::num::{ParseFloatError, ParseIntError};
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -16,72 +19,611 @@ pub type Result<T> = std::result::Result<T, Error>;
 pub enum Error {
     /// Kernel documentation says that if you get os error 2 that
     /// means a feature is unavailable.
-    #[error("the requested sysfs attribute does not exist")]
-    MissingAttribute,
+    #[error("cannot {op} {}: the attribute does not exist", path.display())]
+    MissingAttribute { path: PathBuf, op: Operation },
     /// Sometimes attributes are unsupported on a platform.
-    #[error("the requested sysfs attribute is not supported on this platform")]
-    UnsupportedAttribute,
+    #[error("cannot {op} {}: the attribute is not supported on this platform", path.display())]
+    UnsupportedAttribute { path: PathBuf, op: Operation },
+    /// The kernel rejected the value that was written (`EINVAL`).
+    #[error("cannot {op} {}: the value is not valid for this attribute", path.display())]
+    InvalidValue { path: PathBuf, op: Operation },
+    /// The device is busy and refused the operation (`EBUSY`).
+    #[error("cannot {op} {}: the device is busy", path.display())]
+    Busy { path: PathBuf, op: Operation },
+    /// Usually means that writing requires root (`EACCES` or `EPERM`).
+    #[error("cannot {op} {}: permission denied", path.display())]
+    PermissionDenied { path: PathBuf, op: Operation },
+    /// The value was refused by validation before being written.
+    #[error("refusing to write {value} to {}: {reason}", path.display())]
+    Rejected {
+        path: PathBuf,
+        value: String,
+        reason: String,
+    },
+    /// The kernel accepted a write, but reading the attribute back shows a
+    /// different value, either because it was rounded or ignored.
+    #[error("wrote {requested:?} to {}, but it reads back as {actual:?}", path.display())]
+    NotApplied {
+        path: PathBuf,
+        requested: String,
+        actual: String,
+    },
+    /// The content of the attribute was not understood by its parser.
+    #[error("failed to parse sysfs attribute {}: {reason} (read {raw:?})", path.display())]
+    Parse {
+        path: PathBuf,
+        raw: String,
+        reason: String,
+    },
+
+    #[error("cannot {op} {}: {source}", path.display())]
+    Io {
+        path: PathBuf,
+        op: Operation,
+        source: std::io::Error,
+    },
+}
+
+// Errors are serialized as their message, so that snapshots of attributes
+// can be serialized with the errors that some of them had.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Error {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// The operation on an attribute that caused an [`Error`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Operation {
+    Read,
+    /// Contains the exact string that was being written.
+    Write(String),
+}
+
+impl std::fmt::Display for Operation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Read => write!(f, "read"),
+            Self::Write(value) => write!(f, "write {value:?} to"),
+        }
+    }
+}
+
+impl Error {
+    /// Wraps an IO error with the path and operation that caused it,
+    /// picking a more specific variant for the error codes that *sysfs*
+    /// uses to communicate with userspace.
+    pub fn io(path: impl Into<PathBuf>, op: Operation, source: std::io::Error) -> Self {
+        let path = path.into();
+        match source.kind() {
+            ErrorKind::NotFound => Self::MissingAttribute { path, op },
+            ErrorKind::Unsupported => Self::UnsupportedAttribute { path, op },
+            ErrorKind::InvalidInput => Self::InvalidValue { path, op },
+            ErrorKind::ResourceBusy => Self::Busy { path, op },
+            ErrorKind::PermissionDenied => Self::PermissionDenied { path, op },
+            _ => Self::Io { path, op, source },
+        }
+    }
+
+    /// The full path of the attribute, after resolving it against
+    /// [`sysfs_root`].
+    pub fn path(&self) -> &Path {
+        match self {
+            Self::MissingAttribute { path, .. }
+            | Self::UnsupportedAttribute { path, .. }
+            | Self::InvalidValue { path, .. }
+            | Self::Busy { path, .. }
+            | Self::PermissionDenied { path, .. }
+            | Self::Rejected { path, .. }
+            | Self::NotApplied { path, .. }
+            | Self::Parse { path, .. }
+            | Self::Io { path, .. } => path,
+        }
+    }
+
+    /// The name of the attribute, which is the last component of the path.
+    pub fn attribute(&self) -> Option<&str> {
+        self.path().file_name().and_then(|name| name.to_str())
+    }
+
+    /// Whether the error occurred while reading or writing, and if writing,
+    /// the value that was written.
+    pub fn operation(&self) -> Operation {
+        match self {
+            Self::MissingAttribute { op, .. }
+            | Self::UnsupportedAttribute { op, .. }
+            | Self::InvalidValue { op, .. }
+            | Self::Busy { op, .. }
+            | Self::PermissionDenied { op, .. }
+            | Self::Io { op, .. } => op.clone(),
+            Self::Rejected { value, .. } => Operation::Write(value.clone()),
+            Self::NotApplied { requested, .. } => Operation::Write(requested.clone()),
+            Self::Parse { .. } => Operation::Read,
+        }
+    }
+}
+
+/// General purpose error for parsing the text of an attribute, intended to
+/// be used as `FromStr::Err` for attribute value types. When returned from
+/// a parser given to [`sysfs_read`], the message becomes the `reason` of
+/// [`Error::Parse`].
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+#[error("{0}")]
+pub struct ParseError(pub String);
+
+impl ParseError {
+    pub fn new(reason: impl Into<String>) -> Self {
+        Self(reason.into())
+    }
+}
+
+impl From<ParseIntError> for ParseError {
+    fn from(e: ParseIntError) -> Self {
+        Self(e.to_string())
+    }
+}
+
+impl From<ParseFloatError> for ParseError {
+    fn from(e: ParseFloatError) -> Self {
+        Self(e.to_string())
+    }
+}
 
-    #[error("encountered IO error: {0}")]
-    Io(#[from] std::io::Error),
+/// The error returned by a `validate` closure given to [`sysfs_validate`].
+#[derive(Debug)]
+pub enum ValidationError {
+    /// The value is not acceptable for the attribute, for the given reason.
+    Rejected(String),
+    /// The value could not be checked, usually because reading another
+    /// attribute that it depends on has failed.
+    Failed(Error),
 }
 
-pub const SYSFS_MAX_ATTR_BYTES: usize = 1024;
+impl ValidationError {
+    pub fn rejected(reason: impl Into<String>) -> Self {
+        Self::Rejected(reason.into())
+    }
+}
+
+impl From<Error> for ValidationError {
+    fn from(e: Error) -> Self {
+        Self::Failed(e)
+    }
+}
+
+/// Rejects values that are out of the inclusive range, for use in
+/// `validate` closures.
+pub fn validate_range<T>(
+    value: &T,
+    range: std::ops::RangeInclusive<T>,
+) -> std::result::Result<(), ValidationError>
+where
+    T: PartialOrd + Display,
+{
+    if range.contains(value) {
+        Ok(())
+    } else {
+        Err(ValidationError::rejected(format!(
+            "must be between {} and {}",
+            range.start(),
+            range.end()
+        )))
+    }
+}
+
+/// The return type of a parser given to [`sysfs_read`].
+///
+/// Infallible parsers may return the value directly. Fallible parsers
+/// return a `Result`, and the error's message is kept as the `reason` of
+/// [`Error::Parse`].
+pub trait ParseOutput<T> {
+    fn into_parse_result(self) -> std::result::Result<T, String>;
+}
+
+impl<T> ParseOutput<T> for T {
+    fn into_parse_result(self) -> std::result::Result<T, String> {
+        Ok(self)
+    }
+}
+
+impl<T, E: Display> ParseOutput<T> for std::result::Result<T, E> {
+    fn into_parse_result(self) -> std::result::Result<T, String> {
+        self.map_err(|e| e.to_string())
+    }
+}
 
-/// # Safety
+/// The maximum number of bytes that will be read from a text attribute.
 ///
-/// This function makes an assumption that the contents of the file at
-/// `file_path` is valid UTF-8 (in fact, it is *expected* to be ASCII).
+/// The kernel allocates a single page for the `show` callback of each
+/// attribute, so nothing larger than `PAGE_SIZE` can be produced. This is
+/// the smallest page size on common architectures.
+pub const SYSFS_MAX_ATTR_BYTES: usize = 4096;
+
+/// The environment variable that is consulted for the root directory of
+/// *sysfs* if none has been set with [`set_sysfs_root`].
+pub const SYSFS_ROOT_VAR: &str = "SYSFS_ROOT";
+
+static SYSFS_ROOT: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// Overrides the directory that all *sysfs* paths are resolved against,
+/// for the remainder of the process. Passing `None` restores the default
+/// behavior of reading [`SYSFS_ROOT_VAR`] from the environment.
 ///
-/// This function is only safe if you are using Linux and have provided a
-/// correct path to the attribute in *sysfs*. No validation is performed.
+/// This is intended for running the API against a tree of fixture files,
+/// where `root` contains the `sys` directory (not the other way around).
+pub fn set_sysfs_root(root: Option<impl Into<PathBuf>>) {
+    let mut guard = SYSFS_ROOT.write().unwrap_or_else(|e| e.into_inner());
+    *guard = root.map(Into::into);
+}
+
+/// Returns the directory that absolute *sysfs* paths are relative to.
 ///
-/// The function that you pass for `parse_ok` will also likely require usage of
-/// `.unwrap()` when parsing the file content as an attribute value.
+/// This is the override from [`set_sysfs_root`] if there is one, otherwise
+/// the value of the [`SYSFS_ROOT_VAR`] environment variable, and finally
+/// the real filesystem root.
+pub fn sysfs_root() -> PathBuf {
+    let guard = SYSFS_ROOT.read().unwrap_or_else(|e| e.into_inner());
+    match &*guard {
+        Some(root) => root.clone(),
+        None => std::env::var_os(SYSFS_ROOT_VAR)
+            .filter(|root| !root.is_empty())
+            .map_or_else(|| PathBuf::from("/"), PathBuf::from),
+    }
+}
+
+/// Resolves an absolute path such as `/sys/class/power_supply` against
+/// [`sysfs_root`]. Everything that touches the filesystem should pass its
+/// path through this function first.
+pub fn sysfs_path(path: impl AsRef<Path>) -> PathBuf {
+    let path = path.as_ref();
+    let relative = path.strip_prefix("/").unwrap_or(path);
+    sysfs_root().join(relative)
+}
+
+/// Reads the raw content of an attribute until EOF, without any decoding or
+/// trimming. This is intended for binary attributes, and for text where the
+/// exact bytes matter.
 ///
-/// It is undefined behavior to use this function with file paths not exposed
-/// through *sysfs*.
-pub unsafe fn sysfs_read<T>(file_path: &str, parse_ok: fn(&str) -> T) -> Result<T> {
-    let mut buf = [0; SYSFS_MAX_ATTR_BYTES];
-    let result = OpenOptions::new()
+/// The path is resolved with [`sysfs_path`] before it is opened.
+pub fn sysfs_read_bytes(file_path: &str) -> Result<Vec<u8>> {
+    let file_path = sysfs_path(file_path);
+    let mut buf = Vec::new();
+    OpenOptions::new()
         .read(true)
-        .open(file_path)
-        .and_then(|mut f| {
-            let bytes_read = f.read(&mut buf)?;
-            // SAFETY: Linux guarantees that all of *sysfs* is valid ASCII.
-            let buf = unsafe { std::str::from_utf8_unchecked(&buf[..bytes_read]) };
-            let buf = buf.trim_end();
-            Ok(buf)
-        });
+        .open(&file_path)
+        .and_then(|mut f| f.read_to_end(&mut buf))
+        .map_err(|e| Error::io(file_path, Operation::Read, e))?;
+    Ok(buf)
+}
 
-    match result {
-        Ok("<unsupported>") => Err(Error::UnsupportedAttribute),
-        Ok(text) => Ok(parse_ok(text)),
-        Err(e) if e.kind() == ErrorKind::NotFound => Err(Error::MissingAttribute),
-        Err(e) => Err(Error::from(e)),
+/// Reads a text attribute and parses it with `parse`.
+///
+/// The file is read until EOF, up to [`SYSFS_MAX_ATTR_BYTES`], and trailing
+/// whitespace (the newline) is removed. The content should be ASCII, but if
+/// it is not valid UTF-8 it is decoded lossily rather than failing.
+///
+/// The function that you pass for `parse` may either return the value
+/// directly, or a `Result` (see [`ParseOutput`]). Errors from the parser are
+/// returned as [`Error::Parse`] along with the text that was read.
+///
+/// The path is resolved with [`sysfs_path`] before it is opened.
+pub fn sysfs_read<T, F, P>(file_path: &str, parse: F) -> Result<T>
+where
+    F: FnOnce(&str) -> P,
+    P: ParseOutput<T>,
+{
+    let file_path = sysfs_path(file_path);
+    let mut buf = Vec::with_capacity(SYSFS_MAX_ATTR_BYTES);
+    OpenOptions::new()
+        .read(true)
+        .open(&file_path)
+        .and_then(|f| f.take(SYSFS_MAX_ATTR_BYTES as u64).read_to_end(&mut buf))
+        .map_err(|e| Error::io(&file_path, Operation::Read, e))?;
+
+    let text = String::from_utf8_lossy(&buf);
+    match text.trim_end() {
+        "<unsupported>" => Err(Error::UnsupportedAttribute {
+            path: file_path,
+            op: Operation::Read,
+        }),
+        text => parse(text)
+            .into_parse_result()
+            .map_err(|reason| Error::Parse {
+                path: file_path,
+                raw: text.to_owned(),
+                reason,
+            }),
     }
 }
 
 /// This is a low-level function which opens a file only if it already exists,
 /// writes a string, and wraps error handling. It does not validate, so ensure
 /// that your input is appropriate for the *sysfs* attribute in question.
+///
+/// The path is resolved with [`sysfs_path`] before it is opened.
+/// Errors carry the path and the value, see [`Error::io`].
 pub fn sysfs_write(file_path: &str, value: impl AsRef<str>) -> Result<()> {
+    let file_path = sysfs_path(file_path);
+    let value = value.as_ref();
     OpenOptions::new()
         .read(false)
         .write(true)
+        // Has no effect on real attributes, but fixture files would
+        // otherwise keep the end of a longer value that was there before.
+        .truncate(true)
         .create(false)
-        .open(file_path)
-        .and_then(|mut f| write!(f, "{}", value.as_ref()))
-        .map_err(|e| {
-            if e.kind() == ErrorKind::NotFound {
-                Error::MissingAttribute
-            } else {
-                Error::from(e)
-            }
+        .open(&file_path)
+        .and_then(|mut f| write!(f, "{}", value))
+        .map_err(|e| Error::io(file_path, Operation::Write(value.to_owned()), e))
+}
+
+/// Writes `requested` to an attribute, and then reads it back with `parse`.
+/// The value that was read is formatted again with `format` and compared to
+/// `requested`, so that the comparison is not affected by the precision of
+/// the parsed type.
+///
+/// Returns the value that was read back if it matches, otherwise
+/// [`Error::NotApplied`] with both strings.
+pub fn sysfs_write_verified<T, R, P, W>(
+    file_path: &str,
+    requested: String,
+    parse: R,
+    format: W,
+) -> Result<T>
+where
+    T: Clone,
+    R: FnOnce(&str) -> P,
+    P: ParseOutput<T>,
+    W: FnOnce(T) -> String,
+{
+    sysfs_write(file_path, &requested)?;
+    let actual = sysfs_read(file_path, parse)?;
+    let actual_text = format(actual.clone());
+    if actual_text == requested {
+        Ok(actual)
+    } else {
+        Err(Error::NotApplied {
+            path: sysfs_path(file_path),
+            requested,
+            actual: actual_text,
         })
+    }
+}
+
+/// Runs `validate` on a value that is about to be written to `file_path`,
+/// and formats it with `write` if it is accepted. A rejection becomes
+/// [`Error::Rejected`], with the value formatted the same way, so that the
+/// message shows what would have been written. This is called by setters
+/// generated with a `let validate = ...` closure.
+pub fn sysfs_validate<T, V, W, S>(file_path: &str, value: T, validate: V, write: W) -> Result<S>
+where
+    V: FnOnce(&T) -> std::result::Result<(), ValidationError>,
+    W: FnOnce(T) -> S,
+    S: AsRef<str>,
+{
+    match validate(&value) {
+        Ok(()) => Ok(write(value)),
+        Err(ValidationError::Rejected(reason)) => Err(Error::Rejected {
+            path: sysfs_path(file_path),
+            value: write(value).as_ref().to_owned(),
+            reason,
+        }),
+        Err(ValidationError::Failed(e)) => Err(e),
+    }
+}
+
+/// Whether the attribute at `file_path` is present, after resolving it with
+/// [`sysfs_path`].
+pub fn sysfs_exists(file_path: &str) -> bool {
+    sysfs_path(file_path).exists()
+}
+
+/// Converts [`Error::MissingAttribute`] into `Ok(None)`, for attributes that
+/// the kernel documents as not present when the driver does not support them.
+pub fn optional<T>(result: Result<T>) -> Result<Option<T>> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(Error::MissingAttribute { .. }) => Ok(None),
+        Err(e) => Err(e),
+    }
 }
 
 pub fn parse_selected(text: &str) -> Option<&str> {
     let (open, close) = (text.find('[')?, text.find(']')?);
     text.get(open + 1..close)
 }
+
+/// An attribute that lists every value that it accepts, with the one that is
+/// in effect surrounded by brackets, such as `s2idle [deep]`. A single value
+/// without brackets is taken as the selected one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Selectable<T> {
+    available: Vec<T>,
+    selected: usize,
+}
+
+impl<T> Selectable<T> {
+    /// The value that is in effect.
+    pub fn selected(&self) -> &T {
+        &self.available[self.selected]
+    }
+
+    /// Every value that can be selected, including the selected one, in the
+    /// order that the kernel lists them.
+    pub fn available(&self) -> &[T] {
+        &self.available
+    }
+
+    pub fn into_selected(self) -> T {
+        let Self {
+            mut available,
+            selected,
+        } = self;
+        available.swap_remove(selected)
+    }
+
+    pub fn is_available(&self, value: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        self.available.contains(value)
+    }
+
+    /// Rejects values that are not available, for use in `validate`
+    /// closures.
+    pub fn validate(&self, value: &T) -> std::result::Result<(), ValidationError>
+    where
+        T: PartialEq + Display,
+    {
+        if self.is_available(value) {
+            Ok(())
+        } else {
+            let available = self
+                .available
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>();
+            Err(ValidationError::rejected(format!(
+                "must be one of {available:?}"
+            )))
+        }
+    }
+}
+
+impl<T> std::str::FromStr for Selectable<T>
+where
+    T: std::str::FromStr,
+    T::Err: Display,
+{
+    type Err = ParseError;
+
+    fn from_str(text: &str) -> std::result::Result<Self, Self::Err> {
+        let mut available = Vec::new();
+        let mut selected = None;
+        for word in text.split_whitespace() {
+            let value = match word.strip_prefix('[').and_then(|w| w.strip_suffix(']')) {
+                Some(value) if selected.is_some() => {
+                    return Err(ParseError::new(format!(
+                        "more than one value is selected: {value:?}"
+                    )));
+                }
+                Some(value) => {
+                    selected = Some(available.len());
+                    value
+                }
+                None => word,
+            };
+            available.push(
+                value
+                    .parse()
+                    .map_err(|e: T::Err| ParseError::new(e.to_string()))?,
+            );
+        }
+        // A single value may be printed without brackets, such as the
+        // `scheduler` of a block device that does not support schedulers.
+        let selected = match selected {
+            Some(selected) => selected,
+            None if available.len() == 1 => 0,
+            None => return Err(ParseError::new("no value is selected")),
+        };
+        Ok(Self {
+            available,
+            selected,
+        })
+    }
+}
+
+// Serialized as a map of the selected value and the available values,
+// rather than the index of the selected value.
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize> serde::Serialize for Selectable<T> {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct as _;
+
+        let mut state = serializer.serialize_struct("Selectable", 2)?;
+        state.serialize_field("selected", self.selected())?;
+        state.serialize_field("available", &self.available)?;
+        state.end()
+    }
+}
+
+// Deserialized from the same map, where the selected value must be one of
+// the available values.
+#[cfg(feature = "serde")]
+impl<'de, T> serde::Deserialize<'de> for Selectable<T>
+where
+    T: serde::Deserialize<'de> + PartialEq,
+{
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        #[serde(rename = "Selectable")]
+        struct Repr<T> {
+            selected: T,
+            available: Vec<T>,
+        }
+
+        let Repr {
+            selected,
+            available,
+        } = Repr::deserialize(deserializer)?;
+        let selected = available
+            .iter()
+            .position(|value| *value == selected)
+            .ok_or_else(|| {
+                serde::de::Error::custom("the selected value is not one of the available values")
+            })?;
+        Ok(Self {
+            available,
+            selected,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selectable_single_selection() {
+        let selectable: Selectable<String> = "s2idle [deep]\n".parse().unwrap();
+        assert_eq!(selectable.selected(), "deep");
+        assert!(selectable.is_available(&"s2idle".to_owned()));
+    }
+
+    #[test]
+    fn selectable_no_selection() {
+        assert!("s2idle deep".parse::<Selectable<String>>().is_err());
+    }
+
+    #[test]
+    fn selectable_single_unbracketed() {
+        let selectable: Selectable<String> = "none\n".parse().unwrap();
+        assert_eq!(selectable.selected(), "none");
+        assert_eq!(selectable.available(), ["none"]);
+    }
+
+    #[test]
+    fn selectable_two_selections() {
+        assert!("[s2idle] [deep]".parse::<Selectable<String>>().is_err());
+    }
+
+    #[test]
+    fn selectable_order() {
+        let selectable: Selectable<String> = "[always] madvise never".parse().unwrap();
+        assert_eq!(selectable.available(), ["always", "madvise", "never"]);
+        assert_eq!(selectable.into_selected(), "always");
+
+        let selectable: Selectable<String> = "always madvise [never]".parse().unwrap();
+        assert_eq!(selectable.available(), ["always", "madvise", "never"]);
+        assert_eq!(selectable.into_selected(), "never");
+    }
+}
@@ -3,13 +3,15 @@
 use proc_macro::TokenStream as TokenStream1;
 use proc_macro2::TokenStream as TokenStream2;
 use quote::{format_ident, quote, ToTokens};
+use syn::ext::IdentExt as _;
 use syn::parse::{Parse, ParseStream};
 use syn::punctuated::Punctuated;
 use syn::spanned::Spanned;
 use syn::{
-    parse_macro_input, parse_quote, Attribute, Block, Error, Expr, ExprClosure, ExprLit, ExprRange,
-    Ident, Item, ItemFn, ItemMod, Lit, LitStr, Local, LocalInit, Meta, MetaList, MetaNameValue,
-    Pat, PatIdent, PatType, RangeLimits, ReturnType, Signature, Stmt, Token, Type, Visibility,
+    parse_macro_input, parse_quote, Attribute, Block, Data, DeriveInput, Error, Expr, ExprClosure,
+    ExprLit, ExprRange, Fields, FnArg, Ident, Item, ItemFn, ItemMod, Lit, LitStr, Local, LocalInit,
+    Meta, MetaList, MetaNameValue, Pat, PatIdent, PatType, RangeLimits, ReturnType, Signature,
+    Stmt, Token, Type, TypeReference, Visibility,
 };
 
 macro_rules! err {
@@ -50,9 +52,20 @@ pub fn sysfs_attrs(args: TokenStream1, item: TokenStream1) -> TokenStream1 {
     }
 }
 
+#[proc_macro_derive(SysfsEnum, attributes(sysfs_enum))]
+pub fn sysfs_enum(item: TokenStream1) -> TokenStream1 {
+    let item = parse_macro_input!(item as DeriveInput);
+
+    match SysfsEnum::try_from(item) {
+        Ok(item) => item.into_token_stream().into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
 #[derive(Clone, Default)]
 struct SysfsAttrArgs {
     sysfs_dir: Option<LitStr>,
+    optional: bool,
 }
 
 #[derive(Clone)]
@@ -62,6 +75,7 @@ struct ItemSysfsAttrFn {
     sig: Signature,
     let_read: Option<Local>,
     let_write: Option<Local>,
+    let_validate: Option<Local>,
     #[allow(dead_code)]
     dots: Token![..],
     block: Box<Block>,
@@ -70,6 +84,8 @@ struct ItemSysfsAttrFn {
 #[derive(Clone)]
 struct SysfsModArgs {
     sysfs_dir: LitStr,
+    handle: Option<Ident>,
+    snapshot: Option<Ident>,
 }
 
 impl SysfsModArgs {
@@ -79,43 +95,77 @@ impl SysfsModArgs {
             return err!(item.span(), "this item must have braced content");
         };
 
+        // Every function that will be generated from a `sysfs` attribute,
+        // to generate the handle and snapshot from.
+        let mut generated = Vec::new();
+
         for item in items.iter_mut() {
             // We do not care about anything besides functions with the `sysfs`
             // attribute.
-            let Item::Fn(ItemFn { attrs, .. }) = item else {
+            let Item::Fn(item_fn) = item else {
                 continue;
             };
 
             // Now check for the attribute.
-            for attr in attrs.iter_mut() {
-                if attr.path().is_ident("sysfs") {
-                    // We must avoid parsing the meta if there are no
-                    // parenthesis. This causes syntax errors even though
-                    // `SysfsAttrsArgs` already handles this case.
-                    if meta_is_empty(&attr.meta) {
-                        let args = SysfsAttrArgs::try_from(self.clone())?;
-                        attr.meta = parse_quote! { sysfs(#args) };
-                        break;
-                    }
-
-                    // Parse the existing `sysfs` arguments.
-                    let mut attr_args: SysfsAttrArgs = attr.parse_args()?;
-
-                    self.update_attr_args(&mut attr_args)?;
+            // Multiple `sysfs` attributes on the same item is UB.
+            let Some(attr) = item_fn
+                .attrs
+                .iter_mut()
+                .find(|attr| attr.path().is_ident("sysfs"))
+            else {
+                continue;
+            };
 
-                    // Replace the original arguments with the modified ones.
-                    if let Meta::List(meta) = &mut attr.meta {
-                        meta.tokens = attr_args.to_token_stream();
-                    } else {
-                        unreachable!();
-                    }
+            // We must avoid parsing the meta if there are no
+            // parenthesis. This causes syntax errors even though
+            // `SysfsAttrsArgs` already handles this case.
+            let attr_args = if meta_is_empty(&attr.meta) {
+                SysfsAttrArgs::try_from(self.clone())?
+            } else {
+                // Parse the existing `sysfs` arguments.
+                let mut attr_args: SysfsAttrArgs = attr.parse_args()?;
+                self.update_attr_args(&mut attr_args)?;
+                attr_args
+            };
 
-                    // Multiple `sysfs` attributes on the same item is UB.
-                    break;
+            // Replace the original arguments with the modified ones.
+            attr.meta = parse_quote! { sysfs(#attr_args) };
+
+            if self.handle.is_some() || self.snapshot.is_some() {
+                let mut item_fn = item_fn.clone();
+                item_fn.attrs.retain(|attr| !attr.path().is_ident("sysfs"));
+                let item = ItemSysfsAttrFn::try_from(item_fn)?;
+                let getter_ident = item.sig.ident.clone();
+                for item_fn in sysfs_attr_fns(&attr_args, item)? {
+                    let is_getter = item_fn.sig.ident == getter_ident;
+                    generated.push(ObjectFn { item_fn, is_getter });
                 }
             }
         }
 
+        if self.handle.is_none() && self.snapshot.is_none() {
+            return Ok(());
+        }
+
+        let object = ObjectFns::new(&self.sysfs_dir, generated)?;
+        if let Some(handle) = &self.handle {
+            let handle = HandleStruct {
+                ident: handle.clone(),
+                sysfs_dir: self.sysfs_dir.clone(),
+                object: object.clone(),
+                snapshot: self.snapshot.clone(),
+            };
+            items.extend(syn::parse2::<syn::File>(handle.to_token_stream())?.items);
+        }
+        if let Some(snapshot) = &self.snapshot {
+            let snapshot = SnapshotStruct {
+                ident: snapshot.clone(),
+                sysfs_dir: self.sysfs_dir.clone(),
+                object,
+            };
+            items.extend(syn::parse2::<syn::File>(snapshot.to_token_stream())?.items);
+        }
+
         Ok(())
     }
 
@@ -140,6 +190,42 @@ impl SysfsModArgs {
     }
 }
 
+/// Discards the attributes.
+fn expr_require_ident(expr: Expr) -> syn::Result<Ident> {
+    match &expr {
+        Expr::Path(syn::ExprPath { path, .. }) => match path.get_ident() {
+            Some(ident) => Ok(ident.clone()),
+            None => err!(expr, "expected an identifier"),
+        },
+        _ => err!(expr, "expected an identifier"),
+    }
+}
+
+/// Returns the names of the arguments in a format string, in order.
+/// These are the keys that identify an object in *sysfs*, such as `cpu` in
+/// `/sys/devices/system/cpu/cpufreq/policy{cpu}`.
+fn format_arg_names(format: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut rest = format;
+    while let Some(open) = rest.find('{') {
+        rest = &rest[open + 1..];
+        // An escaped brace.
+        if let Some(after) = rest.strip_prefix('{') {
+            rest = after;
+            continue;
+        }
+        let Some(close) = rest.find('}') else {
+            break;
+        };
+        let name = rest[..close].split(':').next().unwrap_or_default();
+        if !names.iter().any(|other| other == name) {
+            names.push(name.to_owned());
+        }
+        rest = &rest[close + 1..];
+    }
+    names
+}
+
 /// Discards the attributes.
 fn expr_require_lit_str(expr: Expr) -> syn::Result<LitStr> {
     match expr {
@@ -159,6 +245,9 @@ impl Parse for SysfsAttrArgs {
             // as a comma-punctuated list.
             let _in_token = <Token![in]>::parse(input)?;
             let sysfs_dir = expr_require_lit_str(Expr::parse(input)?)?;
+            if !input.is_empty() {
+                let _comma_token = <Token![,]>::parse(input)?;
+            }
             let mut args = Punctuated::<Meta, Token![,]>::parse_terminated(input)?;
             args.insert(0, parse_quote!(sysfs_dir = #sysfs_dir));
             Self::try_from(args)
@@ -174,23 +263,20 @@ impl TryFrom<Punctuated<Meta, Token![,]>> for SysfsAttrArgs {
     type Error = Error;
 
     fn try_from(args: Punctuated<Meta, Token![,]>) -> syn::Result<Self> {
-        let args_span = args.span();
-
-        let mut sysfs_dir = None;
+        let mut this = Self::default();
 
+        // The `sysfs_dir` is not required here, because it may be provided
+        // by an enclosing `sysfs_attrs` module, or it may be computed at
+        // runtime with a local `SYSFS_DIR`.
         args.into_iter().try_for_each(|arg| match arg {
             Meta::NameValue(MetaNameValue { path, value, .. }) if path.is_ident("sysfs_dir") => {
-                Ok(sysfs_dir = Some(expr_require_lit_str(value)?))
+                Ok(this.sysfs_dir = Some(expr_require_lit_str(value)?))
             }
+            Meta::Path(path) if path.is_ident("optional") => Ok(this.optional = true),
             _ => err!(arg, "unknown meta argument"),
         })?;
 
-        let sysfs_dir =
-            sysfs_dir.ok_or_else(|| Error::new(args_span, "argument `sysfs_dir` is required"))?;
-
-        Ok(Self {
-            sysfs_dir: Some(sysfs_dir),
-        })
+        Ok(this)
     }
 }
 
@@ -199,28 +285,53 @@ impl Parse for SysfsModArgs {
         if input.is_empty() {
             err!(input, "this attribute requires arguments")
         } else if input.peek(Token![in]) {
+            // Same special handling of `in` as for `SysfsAttrArgs`.
             let _in_token = <Token![in]>::parse(input)?;
             let sysfs_dir = expr_require_lit_str(Expr::parse(input)?)?;
-
-            Ok(Self { sysfs_dir })
+            if !input.is_empty() {
+                let _comma_token = <Token![,]>::parse(input)?;
+            }
+            let mut args = Punctuated::<Meta, Token![,]>::parse_terminated(input)?;
+            args.insert(0, parse_quote!(sysfs_dir = #sysfs_dir));
+            Self::try_from(args)
         } else {
-            let mut sysfs_dir = None;
-
             let args = Punctuated::<Meta, Token![,]>::parse_terminated(input)?;
-            args.into_iter().try_for_each(|arg| match arg {
-                Meta::NameValue(MetaNameValue { path, value, .. })
-                    if path.is_ident("sysfs_dir") =>
-                {
-                    Ok(sysfs_dir = Some(expr_require_lit_str(value)?))
-                }
-                _ => err!(arg, "unknown meta argument"),
-            })?;
+            Self::try_from(args)
+        }
+    }
+}
 
-            let sysfs_dir = sysfs_dir
-                .ok_or_else(|| Error::new(input.span(), "argument `sysfs_dir` is required"))?;
+impl TryFrom<Punctuated<Meta, Token![,]>> for SysfsModArgs {
+    type Error = Error;
 
-            Ok(Self { sysfs_dir })
-        }
+    fn try_from(args: Punctuated<Meta, Token![,]>) -> syn::Result<Self> {
+        let args_span = args.span();
+
+        let mut sysfs_dir = None;
+        let mut handle = None;
+        let mut snapshot = None;
+
+        args.into_iter().try_for_each(|arg| match arg {
+            Meta::NameValue(MetaNameValue { path, value, .. }) if path.is_ident("sysfs_dir") => {
+                Ok(sysfs_dir = Some(expr_require_lit_str(value)?))
+            }
+            Meta::NameValue(MetaNameValue { path, value, .. }) if path.is_ident("handle") => {
+                Ok(handle = Some(expr_require_ident(value)?))
+            }
+            Meta::NameValue(MetaNameValue { path, value, .. }) if path.is_ident("snapshot") => {
+                Ok(snapshot = Some(expr_require_ident(value)?))
+            }
+            _ => err!(arg, "unknown meta argument"),
+        })?;
+
+        let sysfs_dir =
+            sysfs_dir.ok_or_else(|| Error::new(args_span, "argument `sysfs_dir` is required"))?;
+
+        Ok(Self {
+            sysfs_dir,
+            handle,
+            snapshot,
+        })
     }
 }
 
@@ -242,40 +353,20 @@ impl TryFrom<ItemFn> for ItemSysfsAttrFn {
         }: ItemFn,
     ) -> Result<Self, Self::Error> {
         // Expect a local `let read = #init`, where the init is expected to be a
-        // function that infallibly transforms a string into the return type of
-        // this function.
-        let let_read = block
-            .stmts
-            .iter()
-            .rposition(|stmt| {
-                matches!(stmt, Stmt::Local(Local {
-                    pat: Pat::Ident(PatIdent { ident, .. }),
-                    init: Some(LocalInit { .. }),
-                    ..
-                }) if ident == "read")
-            })
-            .map(|index| match block.stmts.remove(index) {
-                Stmt::Local(local) => local,
-                _ => unreachable!(),
-            });
+        // function that transforms a string into the return type of this
+        // function, either directly or wrapped in a `Result` if it can fail.
+        let let_read = take_local(&mut block, "read");
 
         // Expect a local `let write = |#ident:#ty|` where init is a closure
         // that forms an arbitrary type as a string suitable for output to
         // the file.
-        let let_write = block
-            .stmts
-            .iter()
-            .rposition(|stmt| {
-                matches!(stmt, Stmt::Local(Local {
-                    pat: Pat::Ident(PatIdent { ident, .. }),
-                    init: Some(LocalInit { .. }),
-                    ..
-                }) if ident == "write")
-            })
-            .map(|index| match block.stmts.remove(index) {
-                Stmt::Local(local) => local,
-                _ => unreachable!(),
-            });
+        let let_write = take_local(&mut block, "write");
+
+        // Optionally expect a local `let validate = |#ident| ...` where init
+        // is a closure that receives a reference to the value given to the
+        // setter, and returns `Err(ValidationError)` if it should not be
+        // written.
+        let let_validate = take_local(&mut block, "validate");
 
         // The dots at the end of the function indicate "et cetera",
         // where the generated content will be put. It is not allowed to have
@@ -301,49 +392,229 @@ impl TryFrom<ItemFn> for ItemSysfsAttrFn {
             sig,
             let_read,
             let_write,
+            let_validate,
             dots,
             block,
         })
     }
 }
 
+/// An enum of the values of an attribute, with the kernel's spelling of
+/// each unit variant. The `other` variant holds any value that is not known.
+struct SysfsEnum {
+    ident: Ident,
+    variants: Vec<(Ident, String)>,
+    other: Option<Ident>,
+}
+
+/// Returns the arguments of every `#[sysfs_enum(...)]` attribute.
+fn sysfs_enum_args(attrs: &[Attribute]) -> syn::Result<Vec<Meta>> {
+    let mut args = Vec::new();
+    for attr in attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("sysfs_enum"))
+    {
+        args.extend(attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)?);
+    }
+    Ok(args)
+}
+
+/// Converts the name of a variant to the case given to `rename_all`.
+fn rename_variant(ident: &Ident, case: &LitStr) -> syn::Result<String> {
+    let name = ident.unraw().to_string();
+
+    // Each uppercase letter starts a new word.
+    let mut words = Vec::new();
+    let mut word = String::new();
+    for ch in name.chars() {
+        if ch.is_uppercase() && !word.is_empty() {
+            words.push(std::mem::take(&mut word));
+        }
+        word.push(ch);
+    }
+    words.push(word);
+
+    let lower = || words.iter().map(|word| word.to_lowercase());
+    let upper = || words.iter().map(|word| word.to_uppercase());
+    Ok(match case.value().as_str() {
+        "lowercase" => name.to_lowercase(),
+        "UPPERCASE" => name.to_uppercase(),
+        "kebab-case" => lower().collect::<Vec<_>>().join("-"),
+        "snake_case" => lower().collect::<Vec<_>>().join("_"),
+        "SCREAMING_SNAKE_CASE" => upper().collect::<Vec<_>>().join("_"),
+        _ => return err!(case, "unsupported case"),
+    })
+}
+
+impl TryFrom<DeriveInput> for SysfsEnum {
+    type Error = Error;
+
+    fn try_from(item: DeriveInput) -> syn::Result<Self> {
+        let Data::Enum(data) = item.data else {
+            return err!(item.ident, "expected an enum");
+        };
+
+        let mut rename_all = None;
+        sysfs_enum_args(&item.attrs)?
+            .into_iter()
+            .try_for_each(|arg| match arg {
+                Meta::NameValue(MetaNameValue { path, value, .. })
+                    if path.is_ident("rename_all") =>
+                {
+                    Ok(rename_all = Some(expr_require_lit_str(value)?))
+                }
+                _ => err!(arg, "unknown meta argument"),
+            })?;
+
+        let mut variants = Vec::new();
+        let mut other = None;
+        for variant in data.variants {
+            let mut rename = None;
+            let mut is_other = false;
+            sysfs_enum_args(&variant.attrs)?
+                .into_iter()
+                .try_for_each(|arg| match arg {
+                    Meta::NameValue(MetaNameValue { path, value, .. })
+                        if path.is_ident("rename") =>
+                    {
+                        Ok(rename = Some(expr_require_lit_str(value)?))
+                    }
+                    Meta::Path(path) if path.is_ident("other") => Ok(is_other = true),
+                    _ => err!(arg, "unknown meta argument"),
+                })?;
+
+            if is_other {
+                if other.is_some() {
+                    return err!(variant, "only one variant can be `other`");
+                }
+                match &variant.fields {
+                    Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {}
+                    _ => return err!(variant, "the `other` variant must hold a `String`"),
+                }
+                other = Some(variant.ident);
+                continue;
+            }
+
+            if !matches!(variant.fields, Fields::Unit) {
+                return err!(variant, "expected a unit variant");
+            }
+            let value = match (rename, &rename_all) {
+                (Some(rename), _) => rename.value(),
+                (None, Some(case)) => rename_variant(&variant.ident, case)?,
+                (None, None) => variant.ident.unraw().to_string(),
+            };
+            variants.push((variant.ident, value));
+        }
+
+        Ok(Self {
+            ident: item.ident,
+            variants,
+            other,
+        })
+    }
+}
+
+/// Removes the last `let #name = #init` statement from the block.
+fn take_local(block: &mut Block, name: &str) -> Option<Local> {
+    block
+        .stmts
+        .iter()
+        .rposition(|stmt| {
+            matches!(stmt, Stmt::Local(Local {
+                pat: Pat::Ident(PatIdent { ident, .. }),
+                init: Some(LocalInit { .. }),
+                ..
+            }) if ident == name)
+        })
+        .map(|index| match block.stmts.remove(index) {
+            Stmt::Local(local) => local,
+            _ => unreachable!(),
+        })
+}
+
 //
 // Code related to generating tokens starts here.
 //
 
 fn sysfs_attr(args: &SysfsAttrArgs, item: ItemSysfsAttrFn) -> syn::Result<TokenStream2> {
     let mut tokens = TokenStream2::new();
-    if let Ok(mut getter) = GetterFunction::try_from(item.clone()) {
+    for item_fn in sysfs_attr_fns(args, item)? {
+        item_fn.to_tokens(&mut tokens);
+    }
+    Ok(tokens)
+}
+
+/// Returns every function that is generated for a single attribute.
+fn sysfs_attr_fns(args: &SysfsAttrArgs, item: ItemSysfsAttrFn) -> syn::Result<Vec<ItemFn>> {
+    let mut tokens = TokenStream2::new();
+    let getter = GetterFunction::try_from(item.clone()).ok();
+    let setter = SetterFunction::try_from(item.clone()).ok();
+    if let (Some(getter), Some(setter)) = (&getter, &setter) {
+        if let Some(mut verified) = VerifiedSetterFunction::new(getter, setter) {
+            if let (Some(sysfs_dir), None) = (&args.sysfs_dir, &verified.sysfs_dir) {
+                verified.sysfs_dir = Some(sysfs_dir.clone())
+            }
+            tokens.extend(verified.to_token_stream());
+        }
+    }
+    if let Some(mut getter) = getter {
         if let (Some(sysfs_dir), None) = (&args.sysfs_dir, &getter.sysfs_dir) {
             getter.sysfs_dir = Some(sysfs_dir.clone())
         }
+        if args.optional {
+            getter.make_optional();
+            tokens.extend(ExistsFunction::from(&getter).to_token_stream());
+        }
         tokens.extend(getter.to_token_stream());
     }
-    if let Ok(mut setter) = SetterFunction::try_from(item.clone()) {
+    if let Some(mut setter) = setter {
         if let (Some(sysfs_dir), None) = (&args.sysfs_dir, &setter.sysfs_dir) {
             setter.sysfs_dir = Some(sysfs_dir.clone())
         }
         tokens.extend(setter.to_token_stream());
     }
-    Ok(tokens)
+    Ok(syn::parse2::<syn::File>(tokens)?
+        .items
+        .into_iter()
+        .filter_map(|item| match item {
+            Item::Fn(item_fn) => Some(item_fn),
+            _ => None,
+        })
+        .collect())
 }
 
 impl ToTokens for SysfsAttrArgs {
     fn to_tokens(&self, tokens: &mut TokenStream2) {
-        let Self { sysfs_dir } = self;
+        let Self {
+            sysfs_dir,
+            optional,
+        } = self;
         let mut args = Punctuated::<Meta, Token![,]>::new();
         if let Some(sysfs_dir) = sysfs_dir {
             args.push(parse_quote!(sysfs_dir = #sysfs_dir));
         }
+        if *optional {
+            args.push(parse_quote!(optional));
+        }
         args.to_tokens(tokens)
     }
 }
 
 impl ToTokens for SysfsModArgs {
     fn to_tokens(&self, tokens: &mut TokenStream2) {
-        let Self { sysfs_dir } = self;
+        let Self {
+            sysfs_dir,
+            handle,
+            snapshot,
+        } = self;
         let mut args = Punctuated::<Meta, Token![,]>::new();
         args.push(parse_quote!(sysfs_dir = #sysfs_dir));
+        if let Some(handle) = handle {
+            args.push(parse_quote!(handle = #handle));
+        }
+        if let Some(snapshot) = snapshot {
+            args.push(parse_quote!(snapshot = #snapshot));
+        }
         args.to_tokens(tokens)
     }
 }
@@ -356,6 +627,7 @@ impl TryFrom<SysfsModArgs> for SysfsAttrArgs {
         // but this may eventually be a fallible operation.
         Ok(Self {
             sysfs_dir: Some(other.sysfs_dir),
+            ..Default::default()
         })
     }
 }
@@ -369,6 +641,17 @@ struct GetterFunction {
     stmts: Vec<Stmt>,
     sysfs_dir: Option<LitStr>,
     sysfs_file: String,
+    optional: bool,
+}
+
+/// Generated alongside optional getters, to check whether the attribute is
+/// present without reading it.
+struct ExistsFunction {
+    vis: Visibility,
+    sig: Signature,
+    stmts: Vec<Stmt>,
+    sysfs_dir: Option<LitStr>,
+    sysfs_file: String,
 }
 
 struct SetterFunction {
@@ -376,6 +659,7 @@ struct SetterFunction {
     vis: Visibility,
     sig: Signature,
     let_write: Local,
+    let_validate: Option<Local>,
     from_ident: Ident,
     #[allow(dead_code)]
     from_type: Box<Type>,
@@ -384,6 +668,55 @@ struct SetterFunction {
     sysfs_file: String,
 }
 
+/// A function generated by `sysfs` inside of a `sysfs_attrs` module.
+#[derive(Clone)]
+struct ObjectFn {
+    item_fn: ItemFn,
+    /// Whether this is the getter, as opposed to a setter or a probe.
+    is_getter: bool,
+}
+
+/// The functions of a `sysfs_attrs` module that are specific to one object,
+/// along with the keys that identify the object. The keys are the format
+/// arguments of the module's `sysfs_dir`, and the types are taken from the
+/// functions that accept them.
+#[derive(Clone)]
+struct ObjectFns {
+    keys: Vec<(Ident, Box<Type>)>,
+    fns: Vec<ObjectFn>,
+}
+
+/// Generated by `sysfs_attrs` when given a `handle` argument, with a method
+/// for every function in `object`.
+struct HandleStruct {
+    ident: Ident,
+    sysfs_dir: LitStr,
+    object: ObjectFns,
+    snapshot: Option<Ident>,
+}
+
+/// Generated by `sysfs_attrs` when given a `snapshot` argument, with a
+/// field for every getter in `object`.
+struct SnapshotStruct {
+    ident: Ident,
+    sysfs_dir: LitStr,
+    object: ObjectFns,
+}
+
+/// Generated when an attribute has both a getter and a setter for the same
+/// type, to write a value and then read back what the kernel has applied.
+struct VerifiedSetterFunction {
+    vis: Visibility,
+    sig: Signature,
+    let_read: Local,
+    let_write: Local,
+    let_validate: Option<Local>,
+    from_ident: Ident,
+    stmts: Vec<Stmt>,
+    sysfs_dir: Option<LitStr>,
+    sysfs_file: String,
+}
+
 fn let_sysfs_path(sysfs_dir: &Option<LitStr>, sysfs_file: &str) -> Stmt {
     let literal = match sysfs_dir {
         Some(sysfs_dir) => format!("{}/{}", sysfs_dir.value(), sysfs_file),
@@ -405,18 +738,73 @@ impl ToTokens for GetterFunction {
             stmts,
             sysfs_dir,
             sysfs_file,
+            optional,
         } = self;
         let let_sysfs_path = let_sysfs_path(sysfs_dir, sysfs_file);
 
+        let mut read_expr = quote! {
+            ::sysfs_lib::sysfs_read::<#into_type, _, _>(&sysfs_path, read)
+        };
+        if *optional {
+            read_expr = quote!(::sysfs_lib::optional(#read_expr));
+        }
+
         tokens.extend(quote! {
             #(#attrs)*
             #vis #sig {
                 #(#stmts)*
                 #let_sysfs_path
                 #let_read
-                unsafe {
-                    ::sysfs_lib::sysfs_read::<#into_type>(&sysfs_path, read)
-                }
+                #read_expr
+            }
+        });
+    }
+}
+
+impl GetterFunction {
+    /// Wraps the return type with `Option`, so that a missing attribute is
+    /// not an error.
+    fn make_optional(&mut self) {
+        let into_type = &self.into_type;
+        self.sig.output = parse_quote!(-> ::sysfs_lib::Result<Option<#into_type>>);
+        self.optional = true;
+    }
+}
+
+impl From<&GetterFunction> for ExistsFunction {
+    fn from(getter: &GetterFunction) -> Self {
+        let mut sig = getter.sig.clone();
+        sig.ident = format_ident!("exists_{}", getter.sig.ident.unraw());
+        sig.output = parse_quote!(-> bool);
+
+        Self {
+            vis: getter.vis.clone(),
+            sig,
+            stmts: getter.stmts.clone(),
+            sysfs_dir: getter.sysfs_dir.clone(),
+            sysfs_file: getter.sysfs_file.clone(),
+        }
+    }
+}
+
+impl ToTokens for ExistsFunction {
+    fn to_tokens(&self, tokens: &mut TokenStream2) {
+        let Self {
+            vis,
+            sig,
+            stmts,
+            sysfs_dir,
+            sysfs_file,
+        } = self;
+        let let_sysfs_path = let_sysfs_path(sysfs_dir, sysfs_file);
+        let doc = format!(" Whether the `{sysfs_file}` attribute is present.");
+
+        tokens.extend(quote! {
+            #[doc = #doc]
+            #vis #sig {
+                #(#stmts)*
+                #let_sysfs_path
+                ::sysfs_lib::sysfs_exists(&sysfs_path)
             }
         });
     }
@@ -429,6 +817,7 @@ impl ToTokens for SetterFunction {
             vis,
             sig,
             let_write,
+            let_validate,
             from_ident,
             from_type: _,
             stmts,
@@ -436,6 +825,13 @@ impl ToTokens for SetterFunction {
             sysfs_file,
         } = self;
         let let_sysfs_path = let_sysfs_path(sysfs_dir, sysfs_file);
+        let text = match let_validate {
+            Some(let_validate) => quote! {
+                #let_validate
+                let text = ::sysfs_lib::sysfs_validate(&sysfs_path, #from_ident, validate, write)?;
+            },
+            None => quote!(let text = write(#from_ident);),
+        };
 
         tokens.extend(quote! {
             #(#attrs)*
@@ -443,11 +839,446 @@ impl ToTokens for SetterFunction {
                 #(#stmts)*
                 #let_sysfs_path
                 #let_write
-                unsafe {
-                    ::sysfs_lib::sysfs_write(&sysfs_path, write(#from_ident))
+                #text
+                ::sysfs_lib::sysfs_write(&sysfs_path, text)
+            }
+        });
+    }
+}
+
+/// Returns the name and type of every typed identifier in the inputs.
+fn sig_inputs(sig: &Signature) -> impl Iterator<Item = (&Ident, &Type)> {
+    sig.inputs.iter().filter_map(|input| match input {
+        FnArg::Typed(PatType { pat, ty, .. }) => match pat.as_ref() {
+            Pat::Ident(PatIdent { ident, .. }) => Some((ident, ty.as_ref())),
+            _ => None,
+        },
+        FnArg::Receiver(_) => None,
+    })
+}
+
+impl ObjectFns {
+    fn new(sysfs_dir: &LitStr, generated: Vec<ObjectFn>) -> syn::Result<Self> {
+        let names = format_arg_names(&sysfs_dir.value());
+        let mut keys: Vec<Option<(Ident, Box<Type>)>> = vec![None; names.len()];
+        let mut fns = Vec::new();
+
+        for object_fn in generated {
+            let mut has_keys = 0;
+            for (ident, ty) in sig_inputs(&object_fn.item_fn.sig) {
+                if let Some(index) = names.iter().position(|name| ident == name) {
+                    keys[index].get_or_insert_with(|| (ident.clone(), Box::new(ty.clone())));
+                    has_keys += 1;
+                }
+            }
+            // Functions that do not take every key are not specific to
+            // one object, so they are left out.
+            if has_keys == names.len() {
+                fns.push(object_fn);
+            }
+        }
+
+        let keys = keys
+            .into_iter()
+            .zip(&names)
+            .map(|(key, name)| {
+                key.ok_or_else(|| {
+                    Error::new(
+                        sysfs_dir.span(),
+                        format!("no function in this module takes the argument `{name}`"),
+                    )
+                })
+            })
+            .collect::<syn::Result<_>>()?;
+
+        Ok(Self { keys, fns })
+    }
+
+    fn is_key(&self, ident: &Ident) -> bool {
+        self.keys.iter().any(|(key, _)| key == ident)
+    }
+
+    fn key_idents(&self) -> Vec<&Ident> {
+        self.keys.iter().map(|(ident, _)| ident).collect()
+    }
+
+    /// References are stored as their owned type, and borrowed again
+    /// when calling the functions. Anything else must be `Copy`.
+    fn key_fields(&self) -> Vec<TokenStream2> {
+        self.keys
+            .iter()
+            .map(|(ident, ty)| {
+                let ty = match ty.as_ref() {
+                    Type::Reference(TypeReference { elem, .. }) => match elem.as_ref() {
+                        Type::Path(path) if path.path.is_ident("str") => quote!(String),
+                        _ => quote!(<#elem as ::std::borrow::ToOwned>::Owned),
+                    },
+                    _ => ty.to_token_stream(),
+                };
+                quote!(pub #ident: #ty)
+            })
+            .collect()
+    }
+
+    /// The keys as they are accepted by the functions.
+    fn key_inputs(&self) -> Vec<TokenStream2> {
+        self.keys
+            .iter()
+            .map(|(ident, ty)| quote!(#ident: #ty))
+            .collect()
+    }
+
+    /// Initializers for the fields from `key_fields`, given the
+    /// arguments from `key_inputs`.
+    fn key_inits(&self) -> Vec<TokenStream2> {
+        self.keys
+            .iter()
+            .map(|(ident, ty)| match ty.as_ref() {
+                Type::Reference(_) => quote!(#ident: ::std::borrow::ToOwned::to_owned(#ident)),
+                _ => quote!(#ident),
+            })
+            .collect()
+    }
+
+    /// Borrows the fields from `key_fields` as arguments to the functions.
+    fn key_args_from_self(&self) -> Vec<TokenStream2> {
+        self.keys
+            .iter()
+            .map(|(ident, ty)| match ty.as_ref() {
+                Type::Reference(_) => quote!(&self.#ident),
+                _ => quote!(self.#ident),
+            })
+            .collect()
+    }
+}
+
+impl ToTokens for HandleStruct {
+    fn to_tokens(&self, tokens: &mut TokenStream2) {
+        let Self {
+            ident,
+            sysfs_dir,
+            object,
+            snapshot,
+        } = self;
+        let doc = format!(
+            " A handle to the *sysfs* object at `{}`, with a method for each of \
+             its attributes.",
+            sysfs_dir.value()
+        );
+        let key_idents = object.key_idents();
+        let key_fields = object.key_fields();
+        let key_inputs = object.key_inputs();
+        let key_inits = object.key_inits();
+        let key_args = object.key_args_from_self();
+
+        let methods = object.fns.iter().map(|ObjectFn { item_fn, .. }| {
+            let ItemFn {
+                attrs, vis, sig, ..
+            } = item_fn;
+            let docs = attrs.iter().filter(|attr| attr.path().is_ident("doc"));
+            let name = &sig.ident;
+            let output = &sig.output;
+
+            let mut inputs = Vec::new();
+            let mut call_args = Vec::new();
+            let mut key_args = key_args.iter();
+            for (ident, ty) in sig_inputs(sig) {
+                if object.is_key(ident) {
+                    call_args.push(key_args.next().unwrap().clone());
+                } else {
+                    inputs.push(quote!(#ident: #ty));
+                    call_args.push(quote!(#ident));
+                }
+            }
+
+            quote! {
+                #(#docs)*
+                #vis fn #name(&self, #(#inputs),*) #output {
+                    #name(#(#call_args),*)
+                }
+            }
+        });
+
+        let read_all = snapshot.as_ref().map(|snapshot| {
+            quote! {
+                /// Reads every attribute of this object.
+                pub fn read_all(&self) -> #snapshot {
+                    #snapshot::read_all(#(#key_args),*)
                 }
             }
         });
+
+        tokens.extend(quote! {
+            #[doc = #doc]
+            #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+            pub struct #ident {
+                #(#key_fields,)*
+            }
+
+            impl #ident {
+                pub fn new(#(#key_inputs),*) -> Self {
+                    Self { #(#key_inits),* }
+                }
+
+                #read_all
+
+                #(#methods)*
+            }
+
+            impl ::std::fmt::Display for #ident {
+                fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                    let Self { #(#key_idents),* } = self;
+                    write!(f, #sysfs_dir)
+                }
+            }
+        });
+    }
+}
+
+impl ToTokens for SnapshotStruct {
+    fn to_tokens(&self, tokens: &mut TokenStream2) {
+        let Self {
+            ident,
+            sysfs_dir,
+            object,
+        } = self;
+        let doc = format!(
+            " Every attribute of the *sysfs* object at `{}`, as read by \
+             [`{ident}::read_all`].",
+            sysfs_dir.value()
+        );
+        let key_fields = object.key_fields();
+        let key_inputs = object.key_inputs();
+        let key_inits = object.key_inits();
+
+        // Only getters that take nothing but the keys can be called.
+        let getters = object.fns.iter().filter(|object_fn| {
+            object_fn.is_getter
+                && sig_inputs(&object_fn.item_fn.sig).all(|(ident, _)| object.is_key(ident))
+        });
+        let (fields, inits): (Vec<_>, Vec<_>) = getters
+            .map(|ObjectFn { item_fn, .. }| {
+                let ItemFn { attrs, sig, .. } = item_fn;
+                let docs = attrs.iter().filter(|attr| attr.path().is_ident("doc"));
+                let name = &sig.ident;
+                let ty = match &sig.output {
+                    ReturnType::Type(_, ty) => ty.to_token_stream(),
+                    ReturnType::Default => quote!(()),
+                };
+                let call_args = sig_inputs(sig).map(|(ident, _)| ident);
+
+                let field = quote! {
+                    #(#docs)*
+                    pub #name: #ty
+                };
+                let init = quote!(#name: #name(#(#call_args),*));
+                (field, init)
+            })
+            .unzip();
+
+        // The crate that uses this macro is expected to have a `serde`
+        // feature that enables serialization of the attribute types.
+        tokens.extend(quote! {
+            #[doc = #doc]
+            #[derive(Debug)]
+            #[cfg_attr(feature = "serde", derive(::serde::Serialize))]
+            pub struct #ident {
+                #(#key_fields,)*
+                #(#fields,)*
+            }
+
+            impl #ident {
+                /// Reads every attribute of the object identified by the
+                /// arguments.
+                pub fn read_all(#(#key_inputs),*) -> Self {
+                    Self {
+                        #(#inits,)*
+                        #(#key_inits,)*
+                    }
+                }
+            }
+        });
+    }
+}
+
+impl ToTokens for SysfsEnum {
+    fn to_tokens(&self, tokens: &mut TokenStream2) {
+        let Self {
+            ident,
+            variants,
+            other,
+        } = self;
+        let (idents, values): (Vec<_>, Vec<_>) = variants
+            .iter()
+            .map(|(ident, value)| (ident, value.as_str()))
+            .unzip();
+
+        // Without an `other` variant, parsing an unknown value is an error.
+        let parse_impls = match other {
+            Some(other) => quote! {
+                impl ::std::convert::From<&str> for #ident {
+                    fn from(text: &str) -> Self {
+                        match text {
+                            #(#values => Self::#idents,)*
+                            _ => Self::#other(text.to_owned()),
+                        }
+                    }
+                }
+
+                impl ::std::str::FromStr for #ident {
+                    type Err = ::std::convert::Infallible;
+
+                    fn from_str(text: &str) -> ::std::result::Result<Self, Self::Err> {
+                        Ok(Self::from(text))
+                    }
+                }
+            },
+            None => quote! {
+                impl ::std::str::FromStr for #ident {
+                    type Err = ::sysfs_lib::ParseError;
+
+                    fn from_str(text: &str) -> ::std::result::Result<Self, Self::Err> {
+                        match text {
+                            #(#values => Ok(Self::#idents),)*
+                            _ => Err(::sysfs_lib::ParseError::new(format!(
+                                "unknown value {text:?}"
+                            ))),
+                        }
+                    }
+                }
+            },
+        };
+        let other_arm = other
+            .as_ref()
+            .map(|other| quote!(Self::#other(value) => value,));
+        let is_known = match other {
+            Some(other) => quote!(!matches!(self, Self::#other(_))),
+            None => quote!(true),
+        };
+
+        // Same as for `SnapshotStruct`, the crate is expected to have a
+        // `serde` feature.
+        tokens.extend(quote! {
+            impl #ident {
+                /// Every value that is known to this crate, as spelled by the
+                /// kernel. Newer kernels may report values that are not here.
+                pub const KNOWN_VALUES: &'static [&'static str] = &[#(#values),*];
+
+                /// Returns the value as spelled by the kernel.
+                pub fn as_str(&self) -> &str {
+                    match self {
+                        #(Self::#idents => #values,)*
+                        #other_arm
+                    }
+                }
+
+                /// Returns `false` if the value is not one of
+                /// [`Self::KNOWN_VALUES`].
+                pub fn is_known(&self) -> bool {
+                    #is_known
+                }
+            }
+
+            #parse_impls
+
+            impl ::std::fmt::Display for #ident {
+                fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                    f.write_str(self.as_str())
+                }
+            }
+
+            #[cfg(feature = "serde")]
+            impl ::serde::Serialize for #ident {
+                fn serialize<S: ::serde::Serializer>(
+                    &self,
+                    serializer: S,
+                ) -> ::std::result::Result<S::Ok, S::Error> {
+                    serializer.serialize_str(self.as_str())
+                }
+            }
+
+            #[cfg(feature = "serde")]
+            impl<'de> ::serde::Deserialize<'de> for #ident {
+                fn deserialize<D: ::serde::Deserializer<'de>>(
+                    deserializer: D,
+                ) -> ::std::result::Result<Self, D::Error> {
+                    let text =
+                        <::std::string::String as ::serde::Deserialize>::deserialize(deserializer)?;
+                    text.parse().map_err(::serde::de::Error::custom)
+                }
+            }
+        });
+    }
+}
+
+impl VerifiedSetterFunction {
+    /// Comparing the values is done by formatting both with the `write`
+    /// closure, so the getter must return the same type that the setter
+    /// accepts. Returns `None` if it does not.
+    fn new(getter: &GetterFunction, setter: &SetterFunction) -> Option<Self> {
+        let into_type = &getter.into_type;
+        if into_type.to_token_stream().to_string() != setter.from_type.to_token_stream().to_string()
+        {
+            return None;
+        }
+
+        let mut sig = setter.sig.clone();
+        sig.ident = format_ident!("{}_verified", setter.sig.ident);
+        sig.output = parse_quote!(-> ::sysfs_lib::Result<#into_type>);
+
+        Some(Self {
+            vis: setter.vis.clone(),
+            sig,
+            let_read: getter.let_read.clone(),
+            let_write: setter.let_write.clone(),
+            let_validate: setter.let_validate.clone(),
+            from_ident: setter.from_ident.clone(),
+            stmts: setter.stmts.clone(),
+            sysfs_dir: setter.sysfs_dir.clone(),
+            sysfs_file: setter.sysfs_file.clone(),
+        })
+    }
+}
+
+impl ToTokens for VerifiedSetterFunction {
+    fn to_tokens(&self, tokens: &mut TokenStream2) {
+        let Self {
+            vis,
+            sig,
+            let_read,
+            let_write,
+            let_validate,
+            from_ident,
+            stmts,
+            sysfs_dir,
+            sysfs_file,
+        } = self;
+        let let_sysfs_path = let_sysfs_path(sysfs_dir, sysfs_file);
+        let text = match let_validate {
+            Some(let_validate) => quote! {
+                #let_validate
+                let text = ::sysfs_lib::sysfs_validate(&sysfs_path, #from_ident, validate, write)?;
+            },
+            None => quote!(let text = write(#from_ident);),
+        };
+        let doc = format!(
+            " Writes the `{sysfs_file}` attribute, and then reads it back to \
+             return the value that the kernel has actually applied. If that \
+             differs from the value that was written, \
+             `Error::NotApplied` is returned."
+        );
+
+        tokens.extend(quote! {
+            #[doc = #doc]
+            #vis #sig {
+                #(#stmts)*
+                #let_sysfs_path
+                #let_read
+                #let_write
+                #text
+                ::sysfs_lib::sysfs_write_verified(&sysfs_path, text, read, write)
+            }
+        });
     }
 }
 
@@ -465,7 +1296,7 @@ impl TryFrom<ItemSysfsAttrFn> for GetterFunction {
         }: ItemSysfsAttrFn,
     ) -> syn::Result<Self> {
         if let Some(mut local) = let_read {
-            let sysfs_file = sig.ident.to_string();
+            let sysfs_file = sig.ident.unraw().to_string();
 
             // Take all attributes from the local, and apply them to the function
             // instead. The local assignment will not retain attributes.
@@ -491,6 +1322,7 @@ impl TryFrom<ItemSysfsAttrFn> for GetterFunction {
                 stmts: block.stmts,
                 sysfs_dir: None,
                 sysfs_file,
+                optional: false,
             })
         } else {
             err!(block, "expected to find `let read = ...`")
@@ -507,11 +1339,12 @@ impl TryFrom<ItemSysfsAttrFn> for SetterFunction {
             vis,
             mut sig,
             let_write,
+            let_validate,
             block,
             ..
         }: ItemSysfsAttrFn,
     ) -> syn::Result<Self> {
-        let sysfs_file = sig.ident.to_string();
+        let sysfs_file = sig.ident.unraw().to_string();
 
         let mut local = let_write
             .ok_or_else(|| Error::new(block.span(), "expected to find `let write = ...`"))?;
@@ -549,6 +1382,7 @@ impl TryFrom<ItemSysfsAttrFn> for SetterFunction {
             vis,
             sig,
             let_write: local,
+            let_validate,
             from_ident,
             from_type,
             stmts: block.stmts,
@@ -0,0 +1,267 @@
+//! Runs the API against a tree of fixture files instead of the real `/sys`.
+//!
+//! The root is global to the process, so every test shares one tree, and
+//! each test only touches its own attributes.
+
+use std::fs;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use sysfs::api::block::queue::{self, IoScheduler};
+use sysfs::api::cpu::cpufreq;
+use sysfs::api::cpu::governor::conservative;
+use sysfs::api::cpu::intel_pstate;
+use sysfs::api::cpu::topology;
+use sysfs::api::psu::power_supply::{self, UsbType};
+use sysfs::lib::set_sysfs_root;
+use sysfs::units::{Frequency, Percent};
+use sysfs::Error;
+
+const POLICY0: &str = "sys/devices/system/cpu/cpufreq/policy0";
+const UCSI: &str = "sys/class/power_supply/ucsi-source-psy-USBC000:001";
+
+fn fixture_root() -> &'static PathBuf {
+    static ROOT: OnceLock<PathBuf> = OnceLock::new();
+    ROOT.get_or_init(|| {
+        let root = std::env::temp_dir().join(format!("sysfs-fixtures-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let policy = root.join(POLICY0);
+        fs::create_dir_all(&policy).unwrap();
+        for (name, value) in [
+            ("cpuinfo_min_freq", "400000\n"),
+            ("cpuinfo_max_freq", "4000000\n"),
+            ("scaling_min_freq", "3000000\n"),
+            ("scaling_max_freq", "4000000\n"),
+            ("scaling_driver", "amd-pstate-epp\n"),
+        ] {
+            fs::write(policy.join(name), value).unwrap();
+        }
+        fs::create_dir_all(policy.join("stats")).unwrap();
+        fs::write(
+            policy.join("stats/time_in_state"),
+            "3600000 100\n3400000 25\n",
+        )
+        .unwrap();
+        fs::write(policy.join("stats/reset"), "").unwrap();
+        fs::write(root.join("sys/devices/system/cpu/cpufreq/boost"), "0\n").unwrap();
+        let psu = root.join(UCSI);
+        fs::create_dir_all(&psu).unwrap();
+        fs::write(psu.join("usb_type"), "Unknown SDP [DCP] CDP\n").unwrap();
+        let conservative = root.join("sys/devices/system/cpu/cpufreq/conservative");
+        fs::create_dir_all(&conservative).unwrap();
+        fs::write(conservative.join("up_threshold"), "50\n").unwrap();
+        fs::write(conservative.join("down_threshold"), "20\n").unwrap();
+        let intel_pstate = root.join("sys/devices/system/cpu/intel_pstate");
+        fs::create_dir_all(&intel_pstate).unwrap();
+        fs::write(intel_pstate.join("max_perf_pct"), "100\n").unwrap();
+        fs::write(intel_pstate.join("min_perf_pct"), "50\n").unwrap();
+        // Devices that do not support schedulers print a bare `none`.
+        let dm = root.join("sys/block/dm-0/queue");
+        fs::create_dir_all(&dm).unwrap();
+        fs::write(dm.join("scheduler"), "none\n").unwrap();
+        set_sysfs_root(Some(&root));
+        root
+    })
+}
+
+#[test]
+fn read() {
+    fixture_root();
+    assert_eq!(cpufreq::scaling_driver(0).unwrap(), "amd-pstate-epp");
+    assert_eq!(
+        cpufreq::cpuinfo_max_freq(0).unwrap(),
+        Frequency::from_mhz(4000)
+    );
+}
+
+#[test]
+fn write_and_read_back() {
+    let root = fixture_root();
+    // Shorter than the value that is already there, which must not be
+    // left behind.
+    let applied = cpufreq::set_scaling_min_freq_verified(0, Frequency::from_mhz(400)).unwrap();
+    assert_eq!(applied, Frequency::from_mhz(400));
+    assert_eq!(
+        fs::read_to_string(root.join(POLICY0).join("scaling_min_freq")).unwrap(),
+        "400000"
+    );
+    assert_eq!(
+        cpufreq::scaling_min_freq(0).unwrap(),
+        Frequency::from_mhz(400)
+    );
+}
+
+#[test]
+fn missing_attribute() {
+    fixture_root();
+    assert_eq!(cpufreq::bios_limit(0).unwrap(), None);
+    assert!(!cpufreq::exists_bios_limit(0));
+    match cpufreq::scaling_driver(1) {
+        Err(Error::MissingAttribute { path, .. }) => {
+            assert!(path.ends_with("cpufreq/policy1/scaling_driver"));
+        }
+        other => panic!("expected a missing attribute, got {other:?}"),
+    }
+}
+
+#[test]
+fn read_selectable() {
+    fixture_root();
+    let psu = UCSI.rsplit('/').next().unwrap();
+    let usb_type = power_supply::usb_type(psu).unwrap().unwrap();
+    assert_eq!(usb_type.selected(), &UsbType::Dcp);
+    assert_eq!(usb_type.available().len(), 4);
+}
+
+#[test]
+fn rejected_before_writing() {
+    fixture_root();
+    let err = cpufreq::set_scaling_min_freq(0, Frequency::from_mhz(5000)).unwrap_err();
+    let Error::Rejected { value, reason, .. } = &err else {
+        panic!("expected a rejection, got {err:?}");
+    };
+    assert_eq!(value, "5000000");
+    assert_eq!(
+        reason,
+        "must not be higher than scaling_max_freq (4.00 GHz)"
+    );
+}
+
+#[test]
+fn read_time_in_state() {
+    fixture_root();
+    let time_in_state = cpufreq::time_in_state(0).unwrap().unwrap();
+    assert_eq!(
+        time_in_state.into_iter().collect::<Vec<_>>(),
+        [
+            (Frequency::from_mhz(3400), Duration::from_millis(250)),
+            (Frequency::from_mhz(3600), Duration::from_secs(1)),
+        ]
+    );
+}
+
+#[test]
+fn write_only_attribute() {
+    let root = fixture_root();
+    cpufreq::reset_stats(0).unwrap();
+    assert_eq!(
+        fs::read_to_string(root.join(POLICY0).join("stats/reset")).unwrap(),
+        "1"
+    );
+}
+
+#[test]
+fn read_time_in_state_overflow() {
+    let root = fixture_root();
+    let policy = root.join("sys/devices/system/cpu/cpufreq/policy2");
+    fs::create_dir_all(policy.join("stats")).unwrap();
+    fs::write(
+        policy.join("stats/time_in_state"),
+        format!("3600000 {}\n", u64::MAX),
+    )
+    .unwrap();
+    assert!(matches!(
+        cpufreq::time_in_state(2),
+        Err(Error::Parse { .. })
+    ));
+}
+
+#[test]
+fn frequency_range_rejected() {
+    let root = fixture_root();
+    let err = cpufreq::set_frequency_range(0, Frequency::from_mhz(3000), Frequency::from_mhz(2000))
+        .unwrap_err();
+    let Error::Rejected { path, value, .. } = &err else {
+        panic!("expected a rejection, got {err:?}");
+    };
+    assert_eq!(path, &root.join(POLICY0).join("scaling_min_freq"));
+    assert_eq!(value, "3000000");
+}
+
+#[test]
+fn read_scheduler_without_brackets() {
+    fixture_root();
+    let scheduler = queue::scheduler("dm-0").unwrap();
+    assert_eq!(scheduler.selected(), &IoScheduler::NoScheduler);
+    assert_eq!(scheduler.available(), [IoScheduler::NoScheduler]);
+}
+
+#[test]
+fn percent_validated_as_written() {
+    let root = fixture_root();
+    // Rounds to 50, which is not lower than up_threshold.
+    let err = conservative::set_down_threshold(0, Percent::new(49.6)).unwrap_err();
+    assert!(matches!(err, Error::Rejected { ref value, .. } if value == "50"));
+
+    conservative::set_down_threshold(0, Percent::new(49.4)).unwrap();
+    assert_eq!(
+        fs::read_to_string(root.join("sys/devices/system/cpu/cpufreq/conservative/down_threshold"))
+            .unwrap(),
+        "49"
+    );
+}
+
+#[test]
+fn percent_accepted_as_written() {
+    let root = fixture_root();
+    // Rounds to 50, which is not lower than min_perf_pct.
+    intel_pstate::global::set_max_perf_pct(Percent::new(49.6)).unwrap();
+    assert_eq!(
+        fs::read_to_string(root.join("sys/devices/system/cpu/intel_pstate/max_perf_pct")).unwrap(),
+        "50"
+    );
+}
+
+#[test]
+fn core_types_with_one_pmu() {
+    let root = fixture_root();
+    let cpu_core = root.join("sys/devices/cpu_core");
+    fs::create_dir_all(&cpu_core).unwrap();
+    fs::write(cpu_core.join("cpus"), "0-7\n").unwrap();
+    assert!(topology::core_types().unwrap().is_empty());
+
+    let cpu_atom = root.join("sys/devices/cpu_atom");
+    fs::create_dir_all(&cpu_atom).unwrap();
+    fs::write(cpu_atom.join("cpus"), "8-11\n").unwrap();
+    let types = topology::core_types().unwrap();
+    assert_eq!(types.len(), 12);
+    assert_eq!(types[&0], topology::CoreType::Performance);
+    assert_eq!(types[&8], topology::CoreType::Efficiency);
+}
+
+#[test]
+fn nearest_available_frequency() {
+    let root = fixture_root();
+    let policy = root.join("sys/devices/system/cpu/cpufreq/policy3");
+    fs::create_dir_all(&policy).unwrap();
+    fs::write(
+        policy.join("scaling_available_frequencies"),
+        "2000000 2400000 2800000 \n",
+    )
+    .unwrap();
+    fs::write(
+        policy.join("scaling_boost_frequencies"),
+        "3200000 3600000 \n",
+    )
+    .unwrap();
+    let nearest = |mhz| cpufreq::nearest_available_frequency(3, Frequency::from_mhz(mhz)).unwrap();
+
+    // Equally close to 2.0 and 2.4 GHz.
+    assert_eq!(nearest(2200), Frequency::from_mhz(2000));
+    assert_eq!(nearest(2300), Frequency::from_mhz(2400));
+    assert_eq!(nearest(1000), Frequency::from_mhz(2000));
+    // Boost is disabled, so the boost frequencies are left out.
+    assert_eq!(nearest(3300), Frequency::from_mhz(2800));
+    assert_eq!(nearest(5000), Frequency::from_mhz(2800));
+
+    fs::write(root.join("sys/devices/system/cpu/cpufreq/boost"), "1\n").unwrap();
+    assert_eq!(nearest(3300), Frequency::from_mhz(3200));
+    assert_eq!(nearest(5000), Frequency::from_mhz(3600));
+
+    // Without a frequency table, the frequency is left to the driver.
+    assert_eq!(
+        cpufreq::nearest_available_frequency(0, Frequency::from_mhz(1234)).unwrap(),
+        Frequency::from_mhz(1234)
+    );
+}